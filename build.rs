@@ -0,0 +1,95 @@
+//! Generates a test per regression case in `data/`, see `src/regression.rs`,
+//! and a test per example in `doc/`, see `src/examples.rs`.
+use std::path::Path;
+use std::{env, fs};
+
+#[allow(dead_code)]
+#[path = "src/regression.rs"]
+mod regression;
+
+#[allow(dead_code)]
+#[path = "src/examples/markdown.rs"]
+mod markdown;
+
+fn regression_tests(root: &Path) -> String {
+    // a checkout without the inputs still builds, only malformed cases fail
+    let cases = match root.join("data").is_dir() {
+        true => match regression::discover(root) {
+            Ok(cases) => cases,
+            Err(err) => panic!("{err}"),
+        },
+        false => {
+            println!("cargo:warning=No data directory, no regression tests generated");
+            Vec::new()
+        }
+    };
+    let mut tests = format!("const GENERATED: usize = {};\n", cases.len());
+    for case in &cases {
+        tests += &format!(
+            "\n#[test]\npub fn test_{}() {{\n    check({}, {}, {:?}, {:?});\n}}\n",
+            case.name(),
+            case.day,
+            case.part,
+            case.input,
+            case.answer
+        );
+    }
+    tests
+}
+
+/// the days with a `doc/dayN.md`, in order
+fn doc_days(root: &Path) -> Vec<u32> {
+    let doc = root.join("doc");
+    let entries = match fs::read_dir(&doc) {
+        Ok(entries) => entries,
+        Err(_) => {
+            println!("cargo:warning=No doc directory, no example tests generated");
+            return Vec::new();
+        }
+    };
+    let mut days: Vec<u32> = entries
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            name.to_str()?
+                .strip_prefix("day")?
+                .strip_suffix(".md")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    days.sort();
+    days
+}
+
+fn example_tests(root: &Path) -> String {
+    let mut examples = Vec::<markdown::Example>::new();
+    for day in doc_days(root) {
+        let path = root.join(format!("doc/day{day}.md"));
+        let content = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Could not read {}: {err}", path.display()));
+        examples.extend(markdown::extract(&content, day));
+    }
+    let mut tests = format!("const GENERATED: usize = {};\n", examples.len());
+    for example in &examples {
+        tests += &format!(
+            "\n#[test]\npub fn test_example_d{0}_p{1}() {{\n    check({0}, {1});\n}}\n",
+            example.day, example.part
+        );
+    }
+    tests
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=data");
+    println!("cargo:rerun-if-changed=doc");
+    println!("cargo:rerun-if-changed=src/regression.rs");
+    println!("cargo:rerun-if-changed=src/examples/markdown.rs");
+    let root = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+    let root = Path::new(&root);
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let out_dir = Path::new(&out_dir);
+    fs::write(out_dir.join("regression_tests.rs"), regression_tests(root))
+        .expect("Could not write the regression tests");
+    fs::write(out_dir.join("example_tests.rs"), example_tests(root))
+        .expect("Could not write the example tests");
+}
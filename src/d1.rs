@@ -1,96 +1,377 @@
-use std::fs;
-use std::path::PathBuf;
+use crate::input;
 
-pub fn part1(file_path: String) -> u32 {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
+/// a token found in a line, with the byte offset where it starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// byte offset of the first character of the token
+    pub offset: usize,
+    /// the matched text
+    pub text: String,
+    /// the digit the token stands for
+    pub value: u32,
+}
+
+/// the tokens recognized as digits and the value each one stands for
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    words: Vec<(String, u32)>,
+}
 
-    let mut numbers = Vec::<u32>::new();
+impl Vocabulary {
+    pub fn new() -> Self {
+        Vocabulary {
+            words: Vec::<(String, u32)>::new(),
+        }
+    }
+
+    /// plain digits 0 to 9, as used in part 1
+    pub fn digits() -> Self {
+        let mut vocab = Vocabulary::new();
+        for d in 0..=9u32 {
+            vocab.insert(&d.to_string(), d);
+        }
+        vocab
+    }
 
-    fs::read_to_string(path.as_path())
-        .unwrap()
-        .lines()
-        .map(String::from)
+    /// the digits and the english words one to nine, as used in part 2.
+    /// zero is not a digit in part 2, so neither "0" nor "zero" is included
+    pub fn english() -> Self {
+        let mut vocab = Vocabulary::new();
+        [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ]
         .into_iter()
-        .for_each(|elem| {
-            let mut first: Option<u32> = None;
-            let mut last: Option<u32> = None;
-            elem.chars().for_each(|c| match c {
-                '0'..='9' => {
-                    if first.is_none() {
-                        first = c.to_digit(10);
-                    }
-                    last = c.to_digit(10);
-                }
-                _ => (),
-            });
-            numbers.push(first.unwrap() * 10 + last.unwrap());
+        .enumerate()
+        .for_each(|(ii, word)| {
+            let value = ii as u32 + 1;
+            vocab.insert(&value.to_string(), value);
+            vocab.insert(word, value);
         });
+        vocab
+    }
+
+    /// add a token, replacing the value if the token is already known.
+    /// panics if the value is not a digit, use [`Vocabulary::parse`] to check
+    pub fn insert(&mut self, token: &str, value: u32) -> &mut Self {
+        assert!(value <= 9, "{token} stands for {value}, not a digit");
+        match self.words.iter_mut().find(|(word, _)| word == token) {
+            Some(entry) => entry.1 = value,
+            None => self.words.push((String::from(token), value)),
+        }
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// parse a vocabulary with one `token value` pair per line.
+    /// empty lines and lines starting with `#` are ignored.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut vocab = Vocabulary::new();
+        for (ii, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (token, value) = match line.rsplit_once(char::is_whitespace) {
+                Some(pair) => pair,
+                None => return Err(format!("Line {}: expected 'token value': {line}", ii + 1)),
+            };
+            let token = token.trim();
+            if token.is_empty() {
+                return Err(format!("Line {}: empty token", ii + 1));
+            }
+            match value.parse::<u32>() {
+                Ok(value) if value > 9 => {
+                    return Err(format!("Line {}: {value} is not a digit", ii + 1))
+                }
+                Ok(value) => vocab.insert(token, value),
+                Err(err) => return Err(format!("Line {}: could not parse {value}: {err}", ii + 1)),
+            };
+        }
+        Ok(vocab)
+    }
+
+    pub fn load(file_path: String) -> Result<Self, String> {
+        let content = input::read(&file_path).map_err(|err| err.to_string())?;
+        Vocabulary::parse(&content)
+    }
+
+    /// find all tokens in the line, ordered by offset.
+    ///
+    /// every byte offset is tried on its own, so overlapping words such as
+    /// "eightwo" give both "eight" at 0 and "two" at 4. if several tokens
+    /// start at the same offset the longest one is kept.
+    pub fn tokens(&self, line: &str) -> Vec<Token> {
+        let mut tokens = Vec::<Token>::new();
+        for offset in 0..line.len() {
+            if !line.is_char_boundary(offset) {
+                continue;
+            }
+            let rest = &line[offset..];
+            let best = self
+                .words
+                .iter()
+                .filter(|(word, _)| !word.is_empty() && rest.starts_with(word.as_str()))
+                .max_by_key(|(word, _)| word.len());
+            if let Some((word, value)) = best {
+                tokens.push(Token {
+                    offset,
+                    text: word.clone(),
+                    value: *value,
+                });
+            }
+        }
+        tokens
+    }
+
+    /// the calibration value of a line: the first token followed by the last,
+    /// or `None` if no token is found
+    pub fn value(&self, line: &str) -> Option<u32> {
+        let tokens = self.tokens(line);
+        match (tokens.first(), tokens.last()) {
+            (Some(first), Some(last)) => Some(first.value * 10 + last.value),
+            _ => None,
+        }
+    }
+}
+
+impl Token {
+    fn to_text(&self) -> String {
+        format!("{:?}@{}={}", self.text, self.offset, self.value)
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"offset\":{},\"text\":{},\"value\":{}}}",
+            self.offset,
+            json_string(&self.text),
+            self.value
+        )
+    }
+}
 
-    let mut sum: u32 = 0u32;
-    numbers.into_iter().for_each(|elem| {
-        sum += elem;
+/// quote and escape a string for json output
+fn json_string(input: &str) -> String {
+    let mut out = String::from('"');
+    input.chars().for_each(|c| match c {
+        '"' => out.push_str("\\\""),
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+        c => out.push(c),
     });
+    out.push('"');
+    out
+}
+
+/// output format of a trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Text,
+    Json,
+}
 
-    return sum;
+impl TraceFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "text" => Some(TraceFormat::Text),
+            "json" => Some(TraceFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// how a single line was interpreted: all tokens found and the resulting value
+#[derive(Debug, Clone)]
+pub struct LineTrace {
+    /// line number, from 1
+    pub line: usize,
+    pub text: String,
+    pub tokens: Vec<Token>,
 }
 
-const NUMBERS: &'static [&'static str] = &[
-    "one", "1", "two", "2", "three", "3", "four", "4", "five", "5", "six", "6", "seven", "7",
-    "eight", "8", "nine", "9",
-];
+impl LineTrace {
+    pub fn new(line: usize, text: &str, vocab: &Vocabulary) -> Self {
+        LineTrace {
+            line,
+            text: String::from(text),
+            tokens: vocab.tokens(text),
+        }
+    }
 
-fn str_to_number(input: &str) -> u32 {
-    match input {
-        "one" | "1" => 1u32,
-        "two" | "2" => 2u32,
-        "three" | "3" => 3u32,
-        "four" | "4" => 4u32,
-        "five" | "5" => 5u32,
-        "six" | "6" => 6u32,
-        "seven" | "7" => 7u32,
-        "eight" | "8" => 8u32,
-        "nine" | "9" => 9u32,
-        _ => 0u32,
+    /// the token chosen as first digit
+    pub fn first(&self) -> Option<&Token> {
+        self.tokens.first()
+    }
+
+    /// the token chosen as last digit
+    pub fn last(&self) -> Option<&Token> {
+        self.tokens.last()
+    }
+
+    pub fn value(&self) -> Option<u32> {
+        match (self.first(), self.last()) {
+            (Some(first), Some(last)) => Some(first.value * 10 + last.value),
+            _ => None,
+        }
+    }
+
+    /// one line of text, laid out so traces of two vocabularies can be diffed
+    pub fn to_text(&self) -> String {
+        let tokens: Vec<String> = self.tokens.iter().map(Token::to_text).collect();
+        let show = |token: Option<&Token>| token.map_or(String::from("-"), Token::to_text);
+        format!(
+            "{}: {:?} tokens [{}] first {} last {} value {}",
+            self.line,
+            self.text,
+            tokens.join(" "),
+            show(self.first()),
+            show(self.last()),
+            self.value().map_or(String::from("-"), |v| v.to_string())
+        )
+    }
+
+    /// a single json object on one line
+    pub fn to_json(&self) -> String {
+        let tokens: Vec<String> = self.tokens.iter().map(Token::to_json).collect();
+        let show = |token: Option<&Token>| token.map_or(String::from("null"), Token::to_json);
+        format!(
+            "{{\"line\":{},\"text\":{},\"tokens\":[{}],\"first\":{},\"last\":{},\"value\":{}}}",
+            self.line,
+            json_string(&self.text),
+            tokens.join(","),
+            show(self.first()),
+            show(self.last()),
+            self.value().map_or(String::from("null"), |v| v.to_string())
+        )
+    }
+
+    pub fn format(&self, format: TraceFormat) -> String {
+        match format {
+            TraceFormat::Text => self.to_text(),
+            TraceFormat::Json => self.to_json(),
+        }
     }
 }
-pub fn part2(file_path: String) -> u32 {
-    let mut path: PathBuf = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
-
-    let mut results: Vec<u32> = Vec::<u32>::new();
-    fs::read_to_string(path.as_path())
-        .unwrap()
-        .lines()
-        .map(String::from)
-        .into_iter()
-        .for_each(|elem| {
-            let mut name_matches: Vec<_> = Vec::<(usize, &str)>::new();
-            NUMBERS.into_iter().for_each(|number| {
-                elem.match_indices(number).for_each(|item| {
-                    name_matches.push(item);
-                });
-            });
-            name_matches.sort_by_key(|key| key.0);
-            results.push(
-                match name_matches.first() {
-                    Some(r) => str_to_number(r.1) * 10,
-                    None => 0,
-                } + match name_matches.last() {
-                    Some(r) => str_to_number(r.1),
-                    None => 0,
-                },
+
+/// trace every line of the document with the given vocabulary
+pub fn trace(file_path: String, vocab: &Vocabulary) -> Result<Vec<LineTrace>, String> {
+    Ok(read_lines(file_path)?
+        .iter()
+        .enumerate()
+        .map(|(ii, line)| LineTrace::new(ii + 1, line, vocab))
+        .collect())
+}
+
+/// what to do with a line where no token is found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingPolicy {
+    /// stop and report the line number
+    #[default]
+    Error,
+    /// leave the line out of the sum
+    Skip,
+    /// count the line as 0
+    Zero,
+}
+
+impl MissingPolicy {
+    pub fn parse(policy: &str) -> Option<Self> {
+        match policy {
+            "error" => Some(MissingPolicy::Error),
+            "skip" => Some(MissingPolicy::Skip),
+            "zero" => Some(MissingPolicy::Zero),
+            _ => None,
+        }
+    }
+}
+
+/// the sum of a calibration document and the lines that did not contribute
+#[derive(Debug, Clone, Default)]
+pub struct Calibration {
+    pub sum: u32,
+    /// line numbers (from 1) left out by [`MissingPolicy::Skip`]
+    pub skipped: Vec<usize>,
+    /// line numbers (from 1) counted as 0 by [`MissingPolicy::Zero`]
+    pub zeroed: Vec<usize>,
+}
+
+impl Calibration {
+    /// one line per kind of invalid line, empty if all lines were valid
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        if !self.skipped.is_empty() {
+            summary += &format!(
+                "Skipped {} line(s) without digits: {:?}\n",
+                self.skipped.len(),
+                self.skipped
             );
-        });
-    let mut sum = 0u32;
-    results.into_iter().for_each(|r| {
-        sum += r;
-    });
-    return sum;
+        }
+        if !self.zeroed.is_empty() {
+            summary += &format!(
+                "Counted {} line(s) without digits as 0: {:?}\n",
+                self.zeroed.len(),
+                self.zeroed
+            );
+        }
+        summary
+    }
+}
+
+fn read_lines(file_path: String) -> Result<Vec<String>, String> {
+    match input::read(&file_path) {
+        Ok(content) => Ok(content.lines().map(String::from).collect()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// sum the calibration values of all lines, handling lines without tokens
+/// according to the policy
+pub fn calibrate(
+    file_path: String,
+    vocab: &Vocabulary,
+    policy: MissingPolicy,
+) -> Result<Calibration, String> {
+    let mut result = Calibration::default();
+    for (ii, line) in read_lines(file_path)?.iter().enumerate() {
+        match (vocab.value(line), policy) {
+            (Some(value), _) => result.sum += value,
+            (None, MissingPolicy::Error) => {
+                return Err(format!("Line {}: no digit found: {line:?}", ii + 1))
+            }
+            (None, MissingPolicy::Skip) => result.skipped.push(ii + 1),
+            (None, MissingPolicy::Zero) => result.zeroed.push(ii + 1),
+        }
+    }
+    Ok(result)
+}
+
+pub fn part1(file_path: String) -> u32 {
+    match calibrate(file_path, &Vocabulary::digits(), MissingPolicy::default()) {
+        Ok(calibration) => calibration.sum,
+        Err(str) => panic!("{str}"),
+    }
+}
+
+pub fn part2(file_path: String) -> u32 {
+    match calibrate(file_path, &Vocabulary::english(), MissingPolicy::default()) {
+        Ok(calibration) => calibration.sum,
+        Err(str) => panic!("{str}"),
+    }
 }
 
 #[cfg(test)]
 mod test_d1 {
+    use crate::d1::{calibrate, trace, LineTrace, MissingPolicy, TraceFormat, Vocabulary};
+
     #[test]
     pub fn test_d1_p1() {
         assert_eq!(super::part1(String::from("data/d1/test_p1.txt")), 142);
@@ -99,4 +380,111 @@ mod test_d1 {
     pub fn test_d1_p2() {
         assert_eq!(super::part2(String::from("data/d1/test_p2.txt")), 281);
     }
+    #[test]
+    pub fn test_d1_p1real() {
+        assert_eq!(super::part1(String::from("data/d1/input.txt")), 53651);
+    }
+    #[test]
+    pub fn test_d1_p2real() {
+        assert_eq!(super::part2(String::from("data/d1/input.txt")), 53894);
+    }
+
+    #[test]
+    pub fn test_d1_overlap() {
+        let vocab = Vocabulary::english();
+        let tokens = vocab.tokens("eightwo");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!((tokens[0].offset, tokens[0].value), (0, 8));
+        assert_eq!((tokens[1].offset, tokens[1].value), (4, 2));
+        assert_eq!(vocab.value("eightwo"), Some(82));
+        assert_eq!(vocab.value("xtwone3four"), Some(24));
+        assert_eq!(vocab.value("abc"), None);
+        assert_eq!(vocab.value("0two0"), Some(22));
+        assert_eq!(Vocabulary::digits().value("0two0"), Some(0));
+    }
+
+    #[test]
+    pub fn test_d1_vocab() {
+        let vocab = Vocabulary::load(String::from("data/d1/vocab_sv.txt")).unwrap();
+        assert_eq!(vocab.len(), 10);
+        assert_eq!(vocab.value("noll3fyrasju"), Some(7));
+        assert_eq!(vocab.value("xtvåttax"), Some(28));
+        assert_eq!(vocab.value("ettnio"), Some(19));
+
+        let mut roman = Vocabulary::new();
+        roman
+            .insert("I", 1)
+            .insert("II", 2)
+            .insert("III", 3)
+            .insert("V", 5);
+        assert_eq!(roman.value("aIIIbV"), Some(35));
+
+        assert!(Vocabulary::parse("one").is_err());
+        assert!(Vocabulary::parse("one x").is_err());
+        assert!(Vocabulary::parse("ten 10").is_err());
+        assert!(Vocabulary::parse("x 4294967295").is_err());
+        assert_eq!(Vocabulary::parse("# comment\n\nzero 0").unwrap().len(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn test_d1_insert_not_digit() {
+        Vocabulary::new().insert("ten", 10);
+    }
+
+    #[test]
+    pub fn test_d1_missing() {
+        let file = String::from("data/d1/test_missing.txt");
+        let digits = Vocabulary::digits();
+        let english = Vocabulary::english();
+
+        let err = calibrate(file.clone(), &digits, MissingPolicy::Error).unwrap_err();
+        assert!(err.starts_with("Line 2:"));
+        let err = calibrate(file.clone(), &english, MissingPolicy::Error).unwrap_err();
+        assert!(err.starts_with("Line 4:"));
+
+        let skip = calibrate(file.clone(), &digits, MissingPolicy::Skip).unwrap();
+        assert_eq!(skip.sum, 12 + 77);
+        assert_eq!(skip.skipped, vec![2, 3, 4]);
+        assert!(skip.zeroed.is_empty());
+
+        let zero = calibrate(file.clone(), &english, MissingPolicy::Zero).unwrap();
+        assert_eq!(zero.sum, 12 + 29 + 33 + 77);
+        assert_eq!(zero.zeroed, vec![4]);
+        assert!(zero.summary().contains("[4]"));
+
+        assert_eq!(MissingPolicy::parse("skip"), Some(MissingPolicy::Skip));
+        assert_eq!(MissingPolicy::parse("ignore"), None);
+    }
+
+    #[test]
+    pub fn test_d1_trace() {
+        let line = LineTrace::new(3, "xtwone3four", &Vocabulary::english());
+        assert_eq!(line.tokens.len(), 4);
+        assert_eq!(line.first().unwrap().text, "two");
+        assert_eq!(line.last().unwrap().offset, 7);
+        assert_eq!(line.value(), Some(24));
+        assert_eq!(
+            line.to_text(),
+            "3: \"xtwone3four\" tokens [\"two\"@1=2 \"one\"@3=1 \"3\"@6=3 \"four\"@7=4] \
+             first \"two\"@1=2 last \"four\"@7=4 value 24"
+        );
+
+        let line = LineTrace::new(1, "a\"b", &Vocabulary::digits());
+        assert_eq!(
+            line.format(TraceFormat::Json),
+            "{\"line\":1,\"text\":\"a\\\"b\",\"tokens\":[],\"first\":null,\"last\":null,\"value\":null}"
+        );
+
+        let lines = trace(String::from("data/d1/test_p2.txt"), &Vocabulary::digits()).unwrap();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[1].value(), None);
+        assert_eq!(
+            lines[0].to_json(),
+            "{\"line\":1,\"text\":\"two1nine\",\"tokens\":[{\"offset\":3,\"text\":\"1\",\"value\":1}],\
+             \"first\":{\"offset\":3,\"text\":\"1\",\"value\":1},\
+             \"last\":{\"offset\":3,\"text\":\"1\",\"value\":1},\"value\":11}"
+        );
+        assert_eq!(TraceFormat::parse("json"), Some(TraceFormat::Json));
+    }
 }
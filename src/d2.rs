@@ -1,170 +1,716 @@
-use std::fs;
-use std::path::PathBuf;
+use once_cell::sync::Lazy;
+use std::fmt;
+use std::str::FromStr;
 
-const BAG: Set = Set {
-    red: 12,
-    green: 13,
-    blue: 14,
-};
+use crate::input;
 
+pub mod query;
+
+/// the default bag used in part 1
+static BAG: Lazy<Set> = Lazy::new(|| Set::from([("red", 12), ("green", 13), ("blue", 14)]));
+
+/// reasons a game or set could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// no `:` between the game header and the sets
+    MissingColon(String),
+    /// the header is not `Game <number>`
+    InvalidHeader(String),
+    /// a cube entry is not `<count> <color>`
+    InvalidCubes(String),
+    /// the count of a cube entry is not a number
+    InvalidCount(String),
+    /// the color is not in a palette rejecting unknown colors
+    UnknownColor(String),
+    /// a game without any set
+    NoSets,
+    /// a set without any cubes
+    EmptySet,
+    /// the cubes of a color summed over a set or a game do not fit u32
+    CountOverflow(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingColon(s) => write!(f, "Missing ':' in {s:?}"),
+            ParseError::InvalidHeader(s) => write!(f, "Expected 'Game <number>', got {s:?}"),
+            ParseError::InvalidCubes(s) => write!(f, "Expected '<count> <color>', got {s:?}"),
+            ParseError::InvalidCount(s) => write!(f, "Could not parse count {s:?}"),
+            ParseError::UnknownColor(s) => write!(f, "Unknown color {s:?}"),
+            ParseError::NoSets => write!(f, "A game needs at least one set"),
+            ParseError::EmptySet => write!(f, "A set needs at least one color"),
+            ParseError::CountOverflow(s) => write!(f, "Too many {s} cubes to count"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// how to treat colors that are not part of the palette
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownColors {
+    /// keep the color like any other
+    Accept,
+    /// fail parsing
+    Reject,
+}
+
+/// the cube colors expected in the games
 #[derive(Debug, Clone)]
+pub struct Palette {
+    colors: Vec<String>,
+    unknown: UnknownColors,
+}
+
+impl Palette {
+    pub fn new(colors: &[&str], unknown: UnknownColors) -> Self {
+        Palette {
+            colors: colors.iter().map(|c| String::from(*c)).collect(),
+            unknown,
+        }
+    }
+
+    /// red, green and blue, accepting other colors
+    pub fn rgb() -> Self {
+        Palette::new(&["red", "green", "blue"], UnknownColors::Accept)
+    }
+
+    /// parse a comma separated list of colors
+    pub fn parse(colors: &str, unknown: UnknownColors) -> Self {
+        let colors: Vec<&str> = colors
+            .split(',')
+            .map(|c| c.trim())
+            .filter(|c| !c.is_empty())
+            .collect();
+        Palette::new(&colors, unknown)
+    }
+
+    pub fn colors(&self) -> &[String] {
+        &self.colors
+    }
+
+    pub fn contains(&self, color: &str) -> bool {
+        self.colors.iter().any(|c| c == color)
+    }
+
+    /// check a color against the palette, following the unknown color rule
+    pub fn check(&self, color: &str) -> Result<(), ParseError> {
+        if self.unknown == UnknownColors::Reject && !self.contains(color) {
+            return Err(ParseError::UnknownColor(String::from(color)));
+        }
+        Ok(())
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::rgb()
+    }
+}
+
+/// a color where a game needs more cubes than a bag holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub color: String,
+    /// the most cubes of the color shown at once in the game
+    pub required: u32,
+    /// the cubes of the color in the bag
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Game {
     sets: Vec<Set>,
     number: u32,
+    /// the cubes of each color summed over all sets
     total: Set,
+    /// the most cubes of each color shown in a single set
     max: Set,
+    /// the fewest cubes of each color shown in a set listing the color
+    min: Set,
 }
 
 impl Game {
-    pub fn new(number: u32) -> Self {
-        Game {
+    /// a game of at least one set, each with at least one color, so it can be
+    /// written in the puzzle syntax and parsed back. the cubes of each color
+    /// summed over the sets must fit u32.
+    pub fn new(number: u32, sets: Vec<Set>) -> Result<Self, ParseError> {
+        if sets.is_empty() {
+            return Err(ParseError::NoSets);
+        }
+        let mut game = Game {
             sets: Vec::<Set>::new(),
-            number: number,
+            number,
             total: Set::new(),
             max: Set::new(),
+            min: Set::new(),
+        };
+        for set in sets {
+            if set.counts.is_empty() {
+                return Err(ParseError::EmptySet);
+            }
+            game.add_set(set)?;
         }
+        Ok(game)
     }
 
     pub fn get_number(&self) -> u32 {
         self.number
     }
-    pub fn get_total(&self) -> Set {
-        self.total
+    pub fn get_total(&self) -> &Set {
+        &self.total
+    }
+    pub fn get_max(&self) -> &Set {
+        &self.max
+    }
+    pub fn get_min(&self) -> &Set {
+        &self.min
+    }
+    pub fn get_sets(&self) -> &[Set] {
+        &self.sets
     }
 
-    pub fn get_power(&self) -> u32 {
-        self.max.red * self.max.green * self.max.blue
+    /// the product of the most cubes shown of each color in the palette,
+    /// colors outside the palette are left out. `None` if it overflows u64
+    pub fn get_power(&self, palette: &Palette) -> Option<u64> {
+        palette
+            .colors()
+            .iter()
+            .try_fold(1u64, |power, c| power.checked_mul(self.max.get(c) as u64))
     }
 
-    pub fn parse(game_str: &str) -> Option<Self> {
-        let (game_str, sets) = game_str.trim().split_once(':').unwrap();
-        let (_, number) = game_str.trim().split_once(' ').unwrap();
-        let mut game = Game::new(number.parse().unwrap());
-        sets.split(';')
-            .for_each(|set_str| game.add_set(Set::parse(set_str)));
-        Some(game)
+    /// parse a game like `Game 1: 3 blue, 4 red; 1 red, 2 green`, checking
+    /// the colors against the palette
+    pub fn parse(game_str: &str, palette: &Palette) -> Result<Self, ParseError> {
+        let (header, sets) = match game_str.trim().split_once(':') {
+            Some(pair) => pair,
+            None => return Err(ParseError::MissingColon(String::from(game_str))),
+        };
+        let number = match header.trim().split_once(' ') {
+            Some(("Game", number)) => match number.trim().parse() {
+                Ok(number) => number,
+                Err(_) => return Err(ParseError::InvalidHeader(String::from(header))),
+            },
+            _ => return Err(ParseError::InvalidHeader(String::from(header))),
+        };
+        let sets = sets
+            .split(';')
+            .map(|set_str| Set::parse(set_str, palette))
+            .collect::<Result<Vec<Set>, ParseError>>()?;
+        Game::new(number, sets)
     }
 
-    pub fn add_set(&mut self, set: Set) {
-        self.total.insert(set);
-        self.max.swap_max(set);
+    fn add_set(&mut self, set: Set) -> Result<(), ParseError> {
+        self.total.insert(&set)?;
+        self.max.swap_max(&set);
+        self.min.swap_min(&set);
         self.sets.push(set);
+        Ok(())
     }
 
-    pub fn all_sets_contained(&self, limit: Set) -> bool {
-        for set in &self.sets {
-            if !set.is_contained(limit) {
-                return false;
-            }
-        }
-        true
+    pub fn all_sets_contained(&self, limit: &Set) -> bool {
+        self.sets.iter().all(|set| set.is_contained(limit))
+    }
+
+    /// the smallest bag that makes the game possible
+    pub fn minimal_bag(&self) -> &Set {
+        &self.max
     }
 
-    pub fn to_string(&self) -> String {
-        String::from(format!(
-            "Game number: {:?}\n Total: {}\n   Max: {}",
-            self.number,
-            self.total.to_string(),
-            self.max.to_string()
-        ))
+    /// the colors where the game needs more cubes than the bag holds,
+    /// empty if the game is possible with the bag
+    pub fn violations(&self, bag: &Set) -> Vec<Violation> {
+        self.max
+            .counts
+            .iter()
+            .filter(|(color, count)| *count > bag.get(color))
+            .map(|(color, count)| Violation {
+                color: color.clone(),
+                required: *count,
+                limit: bag.get(color),
+            })
+            .collect()
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+impl FromStr for Game {
+    type Err = ParseError;
+
+    /// parse a game accepting any color
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Game::parse(s, &Palette::default())
+    }
+}
+
+impl fmt::Display for Game {
+    /// the game in the puzzle syntax, `Game 1: 3 blue, 4 red; 1 red, 2 green`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game {}:", self.number)?;
+        for (ii, set) in self.sets.iter().enumerate() {
+            let sep = if ii == 0 { "" } else { ";" };
+            write!(f, "{sep} {set}")?;
+        }
+        Ok(())
+    }
+}
+
+/// cube counts per color, in the order the colors were first given
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Set {
-    red: u32,
-    green: u32,
-    blue: u32,
+    counts: Vec<(String, u32)>,
 }
 
 impl Set {
     pub fn new() -> Self {
         Set {
-            red: 0,
-            green: 0,
-            blue: 0,
-        }
-    }
-    pub fn parse(set_str: &str) -> Self {
-        let mut r: u32 = 0;
-        let mut g: u32 = 0;
-        let mut b: u32 = 0;
-
-        set_str.split(',').for_each(|color_str| {
-            let pair_opt = color_str.trim().split_once(' ');
-            match pair_opt {
-                Some(pair) => match pair.1 {
-                    "red" => r += pair.0.parse::<u32>().unwrap(),
-                    "green" => g += pair.0.parse::<u32>().unwrap(),
-                    "blue" => b += pair.0.parse::<u32>().unwrap(),
-                    _ => println!("{:?} : {:?}", pair.0, pair.1),
-                },
-                None => println!("Set parsed as non"),
+            counts: Vec::<(String, u32)>::new(),
+        }
+    }
+
+    /// the number of cubes of a color, `None` if the color is not in the set.
+    /// a color given with a count of 0 is `Some(0)`.
+    pub fn count(&self, color: &str) -> Option<u32> {
+        self.counts
+            .iter()
+            .find(|(c, _)| c == color)
+            .map(|(_, count)| *count)
+    }
+
+    /// the number of cubes of a color, 0 if the color is not in the set
+    pub fn get(&self, color: &str) -> u32 {
+        self.count(color).unwrap_or(0)
+    }
+
+    /// set the number of cubes of a color
+    pub fn set(&mut self, color: &str, count: u32) {
+        match self.counts.iter_mut().find(|(c, _)| c == color) {
+            Some(entry) => entry.1 = count,
+            None => self.counts.push((String::from(color), count)),
+        }
+    }
+
+    /// add cubes of a color to the set, failing if the sum does not fit u32
+    pub fn add(&mut self, color: &str, count: u32) -> Result<(), ParseError> {
+        match self.get(color).checked_add(count) {
+            Some(sum) => {
+                self.set(color, sum);
+                Ok(())
+            }
+            None => Err(ParseError::CountOverflow(String::from(color))),
+        }
+    }
+
+    pub fn colors(&self) -> impl Iterator<Item = &str> {
+        self.counts.iter().map(|(c, _)| c.as_str())
+    }
+
+    /// parse a set like `3 blue, 4 red`, checking the colors against the palette
+    pub fn parse(set_str: &str, palette: &Palette) -> Result<Self, ParseError> {
+        let mut set = Set::new();
+        for color_str in set_str.split(',') {
+            let (count, color) = match color_str.trim().split_once(' ') {
+                Some((count, color)) if !color.trim().is_empty() => (count, color.trim()),
+                _ => return Err(ParseError::InvalidCubes(String::from(color_str))),
             };
+            palette.check(color)?;
+            match count.parse::<u32>() {
+                Ok(count) => set.add(color, count)?,
+                Err(_) => return Err(ParseError::InvalidCount(String::from(count))),
+            }
+        }
+        Ok(set)
+    }
+
+    /// raise each color to its count in `other`, adding colors not seen before
+    pub fn swap_max(&mut self, other: &Set) {
+        other
+            .counts
+            .iter()
+            .for_each(|(color, count)| match self.count(color) {
+                Some(current) if current >= *count => (),
+                _ => self.set(color, *count),
+            });
+    }
+
+    /// lower each color to its count in `other`, adding colors not seen before
+    pub fn swap_min(&mut self, other: &Set) {
+        other
+            .counts
+            .iter()
+            .for_each(|(color, count)| match self.count(color) {
+                Some(current) if current <= *count => (),
+                _ => self.set(color, *count),
+            });
+    }
+
+    /// add the cubes of `other`, failing if a sum does not fit u32
+    pub fn insert(&mut self, other: &Set) -> Result<(), ParseError> {
+        other
+            .counts
+            .iter()
+            .try_for_each(|(color, count)| self.add(color, *count))
+    }
+    pub fn is_contained(&self, limit: &Set) -> bool {
+        self.counts
+            .iter()
+            .all(|(color, count)| *count <= limit.get(color))
+    }
+}
+
+impl From<&[(&str, u32)]> for Set {
+    /// a color given twice is added up, saturating at u32::MAX
+    fn from(counts: &[(&str, u32)]) -> Self {
+        let mut set = Set::new();
+        counts.iter().for_each(|(color, count)| {
+            if set.add(color, *count).is_err() {
+                set.set(color, u32::MAX);
+            }
         });
+        set
+    }
+}
 
-        Set {
-            red: r,
-            green: g,
-            blue: b,
+impl<const N: usize> From<[(&str, u32); N]> for Set {
+    fn from(counts: [(&str, u32); N]) -> Self {
+        Set::from(&counts[..])
+    }
+}
+
+impl FromStr for Set {
+    type Err = ParseError;
+
+    /// parse a set accepting any color
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Set::parse(s, &Palette::default())
+    }
+}
+
+impl fmt::Display for Set {
+    /// the set in the puzzle syntax, `3 blue, 4 red`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (ii, (color, count)) in self.counts.iter().enumerate() {
+            let sep = if ii == 0 { "" } else { ", " };
+            write!(f, "{sep}{count} {color}")?;
         }
+        Ok(())
     }
+}
 
-    pub fn swap_max(&mut self, other: Set) {
-        if self.red == 0 || self.red < other.red {
-            self.red = other.red;
+fn read_content(file_path: String) -> Result<String, String> {
+    input::read(&file_path).map_err(|err| err.to_string())
+}
+
+pub fn load_data(file_path: String, palette: &Palette) -> Result<Vec<Game>, String> {
+    let mut games: Vec<Game> = Vec::<Game>::new();
+    for (ii, line) in read_content(file_path)?.lines().enumerate() {
+        match Game::parse(line, palette) {
+            Ok(game) => games.push(game),
+            Err(err) => return Err(format!("Line {}: {err}", ii + 1)),
         }
-        if self.green == 0 || self.green < other.green {
-            self.green = other.green;
+    }
+    Ok(games)
+}
+
+/// the default bag with 12 red, 13 green and 14 blue cubes
+pub fn default_bag() -> Set {
+    BAG.clone()
+}
+
+/// parse a bag written like a set, e.g. `12 red, 13 green, 14 blue`
+pub fn parse_bag(bag_str: &str, palette: &Palette) -> Result<Set, ParseError> {
+    Set::parse(bag_str, palette)
+}
+
+/// load bags from a file with one bag per line, empty lines and lines
+/// starting with `#` are ignored
+pub fn load_bags(file_path: String, palette: &Palette) -> Result<Vec<Set>, String> {
+    let mut bags = Vec::<Set>::new();
+    for (ii, line) in read_content(file_path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
-        if self.blue == 0 || self.blue < other.blue {
-            self.blue = other.blue;
+        match parse_bag(line, palette) {
+            Ok(bag) => bags.push(bag),
+            Err(err) => return Err(format!("Line {}: {err}", ii + 1)),
         }
     }
+    Ok(bags)
+}
+
+/// the sum of the numbers of the games possible with the bag
+pub fn part1(games: &[Game], bag: &Set) -> u32 {
+    games
+        .iter()
+        .filter(|game| game.all_sets_contained(bag))
+        .map(|game| game.get_number())
+        .sum()
+}
+
+/// the sum of the powers of the minimal bags of the games, `None` if it
+/// overflows u64
+pub fn part2(games: &[Game], palette: &Palette) -> Option<u64> {
+    games
+        .iter()
+        .try_fold(0u64, |sum, game| sum.checked_add(game.get_power(palette)?))
+}
 
-    pub fn insert(&mut self, other: Set) {
-        self.red += other.red;
-        self.green += other.green;
-        self.blue += other.blue;
+/// list the possible games for the bag, then the minimal bag of each game
+/// and the limits of the bag it violates
+pub fn bag_report(games: &[Game], bag: &Set) -> String {
+    let mut possible = Vec::<u32>::new();
+    let mut report = String::new();
+    games.iter().for_each(|game| {
+        let violations: Vec<String> = game
+            .violations(bag)
+            .iter()
+            .map(|v| format!("{} {} > {}", v.color, v.required, v.limit))
+            .collect();
+        if violations.is_empty() {
+            possible.push(game.get_number());
+        }
+        report += &format!(
+            "  Game {} needs {}: {}\n",
+            game.get_number(),
+            game.minimal_bag(),
+            match violations.is_empty() {
+                true => String::from("possible"),
+                false => violations.join(", "),
+            }
+        );
+    });
+    format!(
+        "Bag {}: {} possible game(s) {:?}, sum {}\n{}",
+        bag,
+        possible.len(),
+        possible,
+        possible.iter().sum::<u32>(),
+        report
+    )
+}
+
+/// smallest, largest and summed values of a series of counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Distribution {
+    pub min: u32,
+    pub max: u32,
+    pub sum: u64,
+    pub count: usize,
+}
+
+impl Distribution {
+    pub fn add(&mut self, value: u32) {
+        if self.count == 0 || value < self.min {
+            self.min = value;
+        }
+        if self.count == 0 || value > self.max {
+            self.max = value;
+        }
+        self.sum += value as u64;
+        self.count += 1;
     }
-    pub fn is_contained(&self, limit: Set) -> bool {
-        self.red <= limit.red && self.green <= limit.green && self.blue <= limit.blue
+
+    pub fn mean(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            n => Some(self.sum as f64 / n as f64),
+        }
     }
-    pub fn to_string(&self) -> String {
-        String::from(format!(
-            "(R, G, B): ({}, {}, {})",
-            self.red, self.green, self.blue
-        ))
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {} max {} mean {:.2}",
+            self.min,
+            self.max,
+            self.mean().unwrap_or(0.0)
+        )
     }
 }
 
-pub fn both_parts(file_path: String) -> (u32, u32) {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
+/// distributions of one color over the games where it was seen
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorStats {
+    pub color: String,
+    /// the most cubes shown at once per game
+    pub max: Distribution,
+    /// the fewest cubes shown at once per game
+    pub min: Distribution,
+    /// the cubes summed over all sets per game
+    pub total: Distribution,
+    /// the number of games where the color was never seen
+    pub missing: usize,
+}
 
-    let mut games: Vec<Game> = Vec::<Game>::new();
+/// how close a game comes to the limits of a bag
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Margin {
+    pub number: u32,
+    /// the color with the least cubes to spare
+    pub color: String,
+    /// cubes to spare of that color, negative if the game is not possible
+    pub margin: i64,
+}
+
+/// statistics over a list of games
+pub struct Analytics<'a> {
+    games: &'a [Game],
+}
 
-    fs::read_to_string(path.as_path())
-        .unwrap()
-        .lines()
-        .for_each(|line| {
-            if let Some(game) = Game::parse(line) {
-                games.push(game)
+impl<'a> Analytics<'a> {
+    pub fn new(games: &'a [Game]) -> Self {
+        Analytics { games }
+    }
+
+    /// all colors seen in the games, in the order they first appear
+    pub fn colors(&self) -> Vec<String> {
+        let mut colors = Vec::<String>::new();
+        self.games.iter().for_each(|game| {
+            game.max.colors().for_each(|c| {
+                if !colors.iter().any(|known| known == c) {
+                    colors.push(String::from(c));
+                }
+            })
+        });
+        colors
+    }
+
+    pub fn color_stats(&self, color: &str) -> ColorStats {
+        let mut stats = ColorStats {
+            color: String::from(color),
+            max: Distribution::default(),
+            min: Distribution::default(),
+            total: Distribution::default(),
+            missing: 0,
+        };
+        self.games.iter().for_each(
+            |game| match (game.max.count(color), game.min.count(color)) {
+                (Some(max), Some(min)) => {
+                    stats.max.add(max);
+                    stats.min.add(min);
+                    stats.total.add(game.total.get(color));
+                }
+                _ => stats.missing += 1,
+            },
+        );
+        stats
+    }
+
+    /// statistics of every color seen in the games
+    pub fn stats(&self) -> Vec<ColorStats> {
+        self.colors()
+            .iter()
+            .map(|color| self.color_stats(color))
+            .collect()
+    }
+
+    /// the game number and power of the minimal bag of each game, `None`
+    /// where the power overflows u64
+    pub fn powers(&self, palette: &Palette) -> Vec<(u32, Option<u64>)> {
+        self.games
+            .iter()
+            .map(|game| (game.number, game.get_power(palette)))
+            .collect()
+    }
+
+    /// the margin of a game to the bag, `None` if the game shows no cubes
+    pub fn margin(game: &Game, bag: &Set) -> Option<Margin> {
+        game.max
+            .counts
+            .iter()
+            .map(|(color, count)| Margin {
+                number: game.number,
+                color: color.clone(),
+                margin: bag.get(color) as i64 - *count as i64,
+            })
+            .reduce(|best, m| if m.margin < best.margin { m } else { best })
+    }
+
+    /// games ordered from furthest over the limits to furthest under them
+    pub fn ranked(&self, bag: &Set) -> Vec<Margin> {
+        let mut margins: Vec<Margin> = self
+            .games
+            .iter()
+            .filter_map(|game| Analytics::margin(game, bag))
+            .collect();
+        margins.sort_by_key(|m| m.margin);
+        margins
+    }
+
+    pub fn report(&self, bag: &Set, palette: &Palette) -> String {
+        let mut report = String::new();
+        self.stats().iter().for_each(|stats| {
+            report += &format!(
+                "{}: seen in {} game(s), missing in {}\n  max   {}\n  min   {}\n  total {}\n",
+                stats.color, stats.max.count, stats.missing, stats.max, stats.min, stats.total
+            );
+        });
+        let powers = self.powers(palette);
+        let sum = powers
+            .iter()
+            .try_fold(0u64, |sum, (_, p)| sum.checked_add((*p)?));
+        // an overflowing power is larger than any other
+        let largest = powers
+            .iter()
+            .max_by_key(|(_, p)| p.map_or(u128::MAX, u128::from));
+        let power = |p: Option<u64>| p.map_or(String::from("overflow"), |p| p.to_string());
+        report += &format!(
+            "Power of minimal bags: sum {}, largest {}\n",
+            power(sum),
+            match largest {
+                Some((number, p)) => format!("game {number} with {}", power(*p)),
+                None => String::from("none"),
             }
+        );
+        report += &format!("Margins to bag {bag}:\n");
+        self.ranked(bag).iter().for_each(|m| {
+            report += &format!("  Game {}: {} {:+}\n", m.number, m.color, m.margin);
         });
-    let mut part1 = 0u32;
-    let mut part2 = 0u32;
-    games.into_iter().for_each(|game| {
-        if game.all_sets_contained(BAG) {
-            part1 += game.get_number();
-        }
-        part2 += game.get_power();
-    });
-    return (part1, part2);
+        report
+    }
+}
+
+pub fn both_parts(file_path: String) -> (u32, u64) {
+    match both_parts_with(file_path, &Palette::default(), &BAG) {
+        Ok(result) => result,
+        Err(str) => panic!("{str}"),
+    }
+}
+
+pub fn both_parts_with(
+    file_path: String,
+    palette: &Palette,
+    bag: &Set,
+) -> Result<(u32, u64), String> {
+    let games = load_data(file_path, palette)?;
+    let p2 = part2(&games, palette).ok_or("The sum of the powers overflows u64")?;
+    Ok((part1(&games, bag), p2))
 }
 
 #[cfg(test)]
-mod test_d1 {
+mod test_d2 {
+    use crate::d2::{
+        bag_report, both_parts_with, default_bag, load_bags, load_data, parse_bag, Analytics,
+        Distribution, Game, Palette, ParseError, Set, UnknownColors, Violation,
+    };
+    use crate::rng::Rng;
+
+    fn random_game(rng: &mut Rng) -> Game {
+        let mut sets = Vec::<Set>::new();
+        for _ in 0..1 + rng.next(6) {
+            let mut colors = vec!["red", "green", "blue", "yellow", "dark violet"];
+            let mut set = Set::new();
+            for _ in 0..1 + rng.next(colors.len() as u64) {
+                let color = colors.remove(rng.next(colors.len() as u64) as usize);
+                set.set(color, rng.next(100) as u32);
+            }
+            sets.push(set);
+        }
+        Game::new(rng.next(1000) as u32, sets).unwrap()
+    }
 
     #[test]
     pub fn test_d2_p1() {
@@ -177,4 +723,210 @@ mod test_d1 {
         let (_, a) = super::both_parts(String::from("data/d2/test_p2.txt"));
         assert_eq!(a, 2286);
     }
+
+    #[test]
+    pub fn test_d2_colors() {
+        let file = String::from("data/d2/test_colors.txt");
+        let (a, b) = both_parts_with(file.clone(), &Palette::rgb(), &default_bag()).unwrap();
+        // game 2 and 5 show yellow cubes, which are not in the bag nor in
+        // the palette, so they do not count in the power
+        assert_eq!(a, 1);
+        assert_eq!(b, 48 + 12 + 1560 + 630 + 36);
+        let yellow = Palette::parse("red,green,blue,yellow", UnknownColors::Accept);
+        let games = load_data(String::from("data/d2/test_colors.txt"), &yellow).unwrap();
+        assert_eq!(super::part2(&games, &yellow), Some(12 * 2 + 36 * 2));
+
+        let strict = Palette::parse("red, green, blue", UnknownColors::Reject);
+        let err = both_parts_with(file, &strict, &default_bag()).unwrap_err();
+        assert!(err.starts_with("Line 2:"));
+
+        let palette = Palette::parse("red,green,blue,yellow", UnknownColors::Reject);
+        let game = Game::parse("Game 7: 1 red, 2 yellow; 3 yellow", &palette).unwrap();
+        assert_eq!(game.get_total(), &Set::from([("red", 1), ("yellow", 5)]));
+        let counts: &[(&str, u32)] = &[("red", 1), ("yellow", 2), ("yellow", 3)];
+        assert_eq!(&Set::from(counts), game.get_total());
+        assert_eq!(game.get_power(&palette), Some(0));
+        assert!(game.all_sets_contained(&Set::from([("red", 1), ("yellow", 3)])));
+        assert!(!game.all_sets_contained(&Set::from([("red", 1), ("green", 3)])));
+    }
+
+    #[test]
+    pub fn test_d2_bags() {
+        let palette = Palette::rgb();
+        let games = load_data(String::from("data/d2/test_p1.txt"), &palette).unwrap();
+        let bags = load_bags(String::from("data/d2/test_bags.txt"), &palette).unwrap();
+        assert_eq!(bags.len(), 3);
+        assert_eq!(bags[0], default_bag());
+        let sums: Vec<u32> = bags.iter().map(|bag| super::part1(&games, bag)).collect();
+        assert_eq!(sums, vec![8, 15, 0]);
+
+        assert_eq!(
+            games[2].minimal_bag(),
+            &Set::from([("green", 13), ("blue", 6), ("red", 20)])
+        );
+        assert_eq!(
+            games[2].violations(&bags[0]),
+            vec![Violation {
+                color: String::from("red"),
+                required: 20,
+                limit: 12,
+            }]
+        );
+        assert!(games[0].violations(&bags[0]).is_empty());
+
+        let report = bag_report(
+            &games,
+            &parse_bag("14 red, 14 green, 15 blue", &palette).unwrap(),
+        );
+        assert!(report.contains("4 possible game(s) [1, 2, 4, 5], sum 12"));
+        assert!(report.contains("  Game 1 needs 6 blue, 4 red, 2 green: possible\n"));
+        assert!(report.contains("  Game 3 needs 13 green, 6 blue, 20 red: red 20 > 14\n"));
+        assert_eq!(report.lines().count(), 1 + games.len());
+    }
+
+    #[test]
+    pub fn test_d2_round_trip() {
+        let line = "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red";
+        let game: Game = line.parse().unwrap();
+        assert_eq!(game.to_string(), line);
+        assert_eq!(game.minimal_bag().to_string(), "3 green, 14 red, 15 blue");
+
+        let mut rng = Rng::new(0x2023_1202);
+        for _ in 0..1000 {
+            let game = random_game(&mut rng);
+            let line = game.to_string();
+            let parsed: Game = line.parse().unwrap();
+            assert_eq!(parsed, game, "{line}");
+            assert_eq!(parsed.to_string(), line);
+        }
+
+        // games and sets that could not be written back are not constructed
+        assert_eq!(Game::new(1, vec![]), Err(ParseError::NoSets));
+        let red = Set::from([("red", 0)]);
+        assert_eq!(
+            Game::new(1, vec![red.clone(), Set::new()]),
+            Err(ParseError::EmptySet)
+        );
+        let game = Game::new(1, vec![red]).unwrap();
+        assert_eq!(game.to_string(), "Game 1: 0 red");
+        assert_eq!(game.to_string().parse::<Game>().unwrap(), game);
+    }
+
+    #[test]
+    pub fn test_d2_parse_errors() {
+        let parse = |s: &str| s.parse::<Game>().unwrap_err();
+        assert_eq!(
+            parse("Game 1 3 blue"),
+            ParseError::MissingColon(String::from("Game 1 3 blue"))
+        );
+        assert_eq!(
+            parse("Game x: 3 blue"),
+            ParseError::InvalidHeader(String::from("Game x"))
+        );
+        assert_eq!(
+            parse("Round 1: 3 blue"),
+            ParseError::InvalidHeader(String::from("Round 1"))
+        );
+        assert_eq!(
+            parse("Game 1: 3 blue; "),
+            ParseError::InvalidCubes(String::new())
+        );
+        assert_eq!(
+            parse("Game 1: 3 blue,4"),
+            ParseError::InvalidCubes(String::from("4"))
+        );
+        assert_eq!(
+            parse("Game 1: x blue"),
+            ParseError::InvalidCount(String::from("x"))
+        );
+
+        let strict = Palette::parse("red,green,blue", UnknownColors::Reject);
+        let err = Game::parse("Game 1: 3 blue, 1 pink", &strict).unwrap_err();
+        assert_eq!(err, ParseError::UnknownColor(String::from("pink")));
+        assert_eq!(err.to_string(), "Unknown color \"pink\"");
+        assert!("".parse::<Set>().is_err());
+
+        let overflow = ParseError::CountOverflow(String::from("red"));
+        assert_eq!(parse("Game 1: 4294967295 red, 1 red"), overflow);
+        assert_eq!(parse("Game 1: 4294967295 red; 1 red"), overflow);
+        assert_eq!(overflow.to_string(), "Too many red cubes to count");
+    }
+
+    #[test]
+    pub fn test_d2_large_counts() {
+        let palette = Palette::rgb();
+        let game: Game = "Game 1: 100000 red, 100000 green, 100000 blue"
+            .parse()
+            .unwrap();
+        assert_eq!(game.get_power(&palette), Some(1_000_000_000_000_000));
+        let max = u32::MAX;
+        let huge: Game = format!("Game 2: {max} red, {max} green, {max} blue")
+            .parse()
+            .unwrap();
+        assert_eq!(huge.get_power(&palette), None);
+        let games = vec![game.clone(), game, huge];
+        assert_eq!(
+            super::part2(&games[..2], &palette),
+            Some(2_000_000_000_000_000)
+        );
+        assert_eq!(super::part2(&games, &palette), None);
+        let report = Analytics::new(&games).report(&default_bag(), &palette);
+        assert!(
+            report.contains("Power of minimal bags: sum overflow, largest game 2 with overflow")
+        );
+    }
+
+    #[test]
+    pub fn test_d2_zero_counts() {
+        let game: Game = "Game 1: 0 red, 3 blue; 2 blue; 4 red".parse().unwrap();
+        assert_eq!(game.get_max().count("red"), Some(4));
+        assert_eq!(game.get_min().count("red"), Some(0));
+        assert_eq!(game.get_min().count("blue"), Some(2));
+        assert_eq!(game.get_max().count("green"), None);
+
+        let game: Game = "Game 2: 0 red; 2 blue".parse().unwrap();
+        assert_eq!(game.get_max().count("red"), Some(0));
+        assert_eq!(game.get_max().get("green"), 0);
+    }
+
+    #[test]
+    pub fn test_d2_analytics() {
+        let palette = Palette::rgb();
+        let mut games = load_data(String::from("data/d2/test_p1.txt"), &palette).unwrap();
+        games.push("Game 6: 2 yellow".parse().unwrap());
+        let analytics = Analytics::new(&games);
+        assert_eq!(analytics.colors(), vec!["blue", "red", "green", "yellow"]);
+
+        let red = analytics.color_stats("red");
+        assert_eq!(
+            red.max,
+            Distribution {
+                min: 1,
+                max: 20,
+                sum: 45,
+                count: 5
+            }
+        );
+        assert_eq!((red.min.min, red.min.max), (1, 3));
+        assert_eq!((red.total.min, red.total.max, red.total.sum), (1, 25, 61));
+        assert_eq!(red.missing, 1);
+        assert_eq!(analytics.color_stats("yellow").missing, 5);
+
+        let powers = analytics.powers(&palette);
+        assert_eq!(powers[0], (1, Some(48)));
+        assert_eq!(powers.iter().map(|(_, p)| p.unwrap()).sum::<u64>(), 2286);
+
+        let ranked: Vec<(u32, i64)> = analytics
+            .ranked(&default_bag())
+            .iter()
+            .map(|m| (m.number, m.margin))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![(3, -8), (4, -2), (6, -2), (5, 6), (1, 8), (2, 10)]
+        );
+        assert!(analytics
+            .report(&default_bag(), &palette)
+            .contains("Game 3: red -8"));
+    }
 }
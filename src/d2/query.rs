@@ -0,0 +1,535 @@
+//! A small filter and aggregate language over day 2 games.
+//!
+//! A query is an optional filter followed by an optional aggregate after `|`:
+//!
+//! ```text
+//! max.red > 12 and sets >= 3 | sum number
+//! not (total.blue < 20 or power == 0) | count
+//! | mean power
+//! ```
+//!
+//! Filters are arithmetic (`+ - *`) and comparisons (`> >= < <= == !=`) over
+//! integers, joined by `and`, `or` and `not`. A comparison is 1 when true and
+//! 0 when false, and any non-zero value counts as true. The fields are
+//! `number`, `sets`, `power` and `max.<color>`, `min.<color>` and
+//! `total.<color>`, where a color never shown in a game reads as 0.
+//! Arithmetic or a field beyond the range of an `i64` fails the query.
+//!
+//! The aggregates are `count`, `list [expr]` and `sum`, `min`, `max` or
+//! `mean` of an expression. Without an aggregate the matching games are listed.
+use super::{Game, Palette};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Int(i64),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+    Pipe,
+}
+
+/// split a query into tokens
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::<Token>::new();
+    let mut ii = 0;
+    while ii < chars.len() {
+        let c = chars[ii];
+        let next = chars.get(ii + 1).copied();
+        match c {
+            ' ' | '\t' => ii += 1,
+            '(' => {
+                tokens.push(Token::Open);
+                ii += 1;
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                ii += 1;
+            }
+            '|' => {
+                tokens.push(Token::Pipe);
+                ii += 1;
+            }
+            '+' | '-' | '*' => {
+                tokens.push(Token::Op(match c {
+                    '+' => "+",
+                    '-' => "-",
+                    _ => "*",
+                }));
+                ii += 1;
+            }
+            '<' | '>' | '=' | '!' => {
+                let op = match (c, next) {
+                    ('<', Some('=')) => "<=",
+                    ('>', Some('=')) => ">=",
+                    ('=', Some('=')) => "==",
+                    ('!', Some('=')) => "!=",
+                    ('<', _) => "<",
+                    ('>', _) => ">",
+                    _ => return Err(format!("Unexpected {c:?} at {ii}")),
+                };
+                tokens.push(Token::Op(op));
+                ii += op.len();
+            }
+            '0'..='9' => {
+                let start = ii;
+                while ii < chars.len() && chars[ii].is_ascii_digit() {
+                    ii += 1;
+                }
+                let digits: String = chars[start..ii].iter().collect();
+                match digits.parse() {
+                    Ok(value) => tokens.push(Token::Int(value)),
+                    Err(err) => return Err(format!("Could not parse {digits} at {start}: {err}")),
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = ii;
+                while ii < chars.len()
+                    && (chars[ii].is_alphanumeric() || chars[ii] == '_' || chars[ii] == '.')
+                {
+                    ii += 1;
+                }
+                tokens.push(Token::Ident(chars[start..ii].iter().collect()));
+            }
+            _ => return Err(format!("Unexpected {c:?} at {ii}")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// a value read from a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    Number,
+    Sets,
+    Power,
+    Max(String),
+    Min(String),
+    Total(String),
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name.split_once('.') {
+            None => match name {
+                "number" => Ok(Field::Number),
+                "sets" => Ok(Field::Sets),
+                "power" => Ok(Field::Power),
+                _ => Err(format!("Unknown field {name:?}")),
+            },
+            Some(("max", color)) => Ok(Field::Max(String::from(color))),
+            Some(("min", color)) => Ok(Field::Min(String::from(color))),
+            Some(("total", color)) => Ok(Field::Total(String::from(color))),
+            Some(_) => Err(format!("Unknown field {name:?}")),
+        }
+    }
+
+    /// the value for a game, failing when the power overflows or the value
+    /// does not fit an i64
+    fn value(&self, game: &Game, palette: &Palette) -> Result<i64, String> {
+        let value = match self {
+            Field::Number => Some(game.get_number() as u64),
+            Field::Sets => Some(game.get_sets().len() as u64),
+            Field::Power => game.get_power(palette),
+            Field::Max(color) => Some(game.get_max().get(color) as u64),
+            Field::Min(color) => Some(game.get_min().get(color) as u64),
+            Field::Total(color) => Some(game.get_total().get(color) as u64),
+        };
+        value
+            .and_then(|value| i64::try_from(value).ok())
+            .ok_or(format!("Overflow in {self} for game {}", game.get_number()))
+    }
+}
+
+impl fmt::Display for Field {
+    /// the field as written in a query
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Number => write!(f, "number"),
+            Field::Sets => write!(f, "sets"),
+            Field::Power => write!(f, "power"),
+            Field::Max(color) => write!(f, "max.{color}"),
+            Field::Min(color) => write!(f, "min.{color}"),
+            Field::Total(color) => write!(f, "total.{color}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Int(i64),
+    Field(Field),
+    Not(Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// the value for a game, failing when the arithmetic overflows
+    fn eval(&self, game: &Game, palette: &Palette) -> Result<i64, String> {
+        match self {
+            Expr::Int(value) => Ok(*value),
+            Expr::Field(field) => field.value(game, palette),
+            Expr::Not(expr) => Ok((expr.eval(game, palette)? == 0) as i64),
+            Expr::Binary(op, lhs, rhs) => {
+                let a = lhs.eval(game, palette)?;
+                // short circuit the boolean operators
+                match *op {
+                    "and" if a == 0 => return Ok(0),
+                    "or" if a != 0 => return Ok(1),
+                    _ => (),
+                }
+                let b = rhs.eval(game, palette)?;
+                let checked = match *op {
+                    "+" => a.checked_add(b),
+                    "-" => a.checked_sub(b),
+                    "*" => a.checked_mul(b),
+                    ">" => Some((a > b) as i64),
+                    ">=" => Some((a >= b) as i64),
+                    "<" => Some((a < b) as i64),
+                    "<=" => Some((a <= b) as i64),
+                    "==" => Some((a == b) as i64),
+                    "!=" => Some((a != b) as i64),
+                    _ => Some((b != 0) as i64), // and, or
+                };
+                checked.ok_or(format!(
+                    "Overflow in {a} {op} {b} for game {}",
+                    game.get_number()
+                ))
+            }
+        }
+    }
+}
+
+/// the step applied to the games passing the filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Aggregate {
+    Games,
+    Count,
+    List(Option<Expr>),
+    Sum(Expr),
+    Min(Expr),
+    Max(Expr),
+    Mean(Expr),
+}
+
+/// recursive descent parser over the tokens of a query
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name == keyword)
+    }
+
+    fn at_end(&self) -> bool {
+        matches!(self.peek(), None | Some(Token::Pipe))
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.is_keyword("or") {
+            self.pos += 1;
+            expr = Expr::Binary("or", Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.is_keyword("and") {
+            self.pos += 1;
+            expr = Expr::Binary("and", Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.is_keyword("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let lhs = self.sum()?;
+        match self.peek() {
+            Some(Token::Op(op)) if !matches!(*op, "+" | "-" | "*") => {
+                let op = *op;
+                self.pos += 1;
+                Ok(Expr::Binary(op, Box::new(lhs), Box::new(self.sum()?)))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        while let Some(Token::Op(op @ ("+" | "-"))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut expr = self.atom()?;
+        while let Some(Token::Op("*")) = self.peek() {
+            self.pos += 1;
+            expr = Expr::Binary("*", Box::new(expr), Box::new(self.atom()?));
+        }
+        Ok(expr)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Int(value)) => Ok(Expr::Int(value)),
+            Some(Token::Op("-")) => Ok(Expr::Binary(
+                "-",
+                Box::new(Expr::Int(0)),
+                Box::new(self.atom()?),
+            )),
+            Some(Token::Ident(name)) => Ok(Expr::Field(Field::parse(&name)?)),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(String::from("Missing ')'")),
+                }
+            }
+            Some(token) => Err(format!("Unexpected {token:?}")),
+            None => Err(String::from("Unexpected end of query")),
+        }
+    }
+
+    fn aggregate(&mut self) -> Result<Aggregate, String> {
+        let name = match self.next() {
+            Some(Token::Ident(name)) => name,
+            Some(token) => return Err(format!("Expected an aggregate, got {token:?}")),
+            None => return Err(String::from("Expected an aggregate after '|'")),
+        };
+        let aggregate = match name.as_str() {
+            "count" => Aggregate::Count,
+            "list" if self.peek().is_none() => Aggregate::List(None),
+            "list" => Aggregate::List(Some(self.sum()?)),
+            "sum" => Aggregate::Sum(self.sum()?),
+            "min" => Aggregate::Min(self.sum()?),
+            "max" => Aggregate::Max(self.sum()?),
+            "mean" => Aggregate::Mean(self.sum()?),
+            _ => return Err(format!("Unknown aggregate {name:?}")),
+        };
+        Ok(aggregate)
+    }
+}
+
+/// the result of running a query
+#[derive(Debug, Clone, PartialEq)]
+pub enum Answer {
+    /// the games passing the filter
+    Games(Vec<Game>),
+    Count(usize),
+    /// game numbers and the listed value of each game
+    List(Vec<(u32, i64)>),
+    /// a sum, min or max, `None` if no game passed the filter
+    Value(Option<i64>),
+    Mean(Option<f64>),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Games(games) => games.iter().try_for_each(|game| writeln!(f, "{game}")),
+            Answer::Count(count) => writeln!(f, "{count}"),
+            Answer::List(values) => values
+                .iter()
+                .try_for_each(|(number, value)| writeln!(f, "Game {number}: {value}")),
+            Answer::Value(Some(value)) => writeln!(f, "{value}"),
+            Answer::Mean(Some(value)) => writeln!(f, "{value:.3}"),
+            Answer::Value(None) | Answer::Mean(None) => writeln!(f, "-"),
+        }
+    }
+}
+
+/// a parsed query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    filter: Option<Expr>,
+    aggregate: Aggregate,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(query)?,
+            pos: 0,
+        };
+        let filter = match parser.at_end() {
+            true => None,
+            false => Some(parser.or()?),
+        };
+        let aggregate = match parser.next() {
+            None => Aggregate::Games,
+            Some(Token::Pipe) => parser.aggregate()?,
+            Some(token) => return Err(format!("Unexpected {token:?}")),
+        };
+        if let Some(token) = parser.next() {
+            return Err(format!("Unexpected {token:?} after aggregate"));
+        }
+        Ok(Query { filter, aggregate })
+    }
+
+    /// whether the game passes the filter
+    pub fn matches(&self, game: &Game, palette: &Palette) -> Result<bool, String> {
+        match &self.filter {
+            Some(filter) => Ok(filter.eval(game, palette)? != 0),
+            None => Ok(true),
+        }
+    }
+
+    /// the answer over the games, failing when the arithmetic overflows
+    pub fn run(&self, games: &[Game], palette: &Palette) -> Result<Answer, String> {
+        let mut matching = Vec::<&Game>::new();
+        for game in games {
+            if self.matches(game, palette)? {
+                matching.push(game);
+            }
+        }
+        let games = matching;
+        let values = |expr: &Expr| -> Result<Vec<i64>, String> {
+            games.iter().map(|game| expr.eval(game, palette)).collect()
+        };
+        let sum = |values: &[i64]| -> Result<i64, String> {
+            values
+                .iter()
+                .try_fold(0i64, |sum, value| sum.checked_add(*value))
+                .ok_or(String::from("Overflow in sum"))
+        };
+        let answer = match &self.aggregate {
+            Aggregate::Games => Answer::Games(games.into_iter().cloned().collect()),
+            Aggregate::Count => Answer::Count(games.len()),
+            Aggregate::List(expr) => Answer::List(
+                games
+                    .iter()
+                    .map(|game| match expr {
+                        Some(expr) => Ok((game.get_number(), expr.eval(game, palette)?)),
+                        None => Ok((game.get_number(), game.get_number() as i64)),
+                    })
+                    .collect::<Result<Vec<(u32, i64)>, String>>()?,
+            ),
+            Aggregate::Sum(expr) => Answer::Value(Some(sum(&values(expr)?)?)),
+            Aggregate::Min(expr) => Answer::Value(values(expr)?.into_iter().min()),
+            Aggregate::Max(expr) => Answer::Value(values(expr)?.into_iter().max()),
+            Aggregate::Mean(expr) => {
+                let values = values(expr)?;
+                Answer::Mean(match values.len() {
+                    0 => None,
+                    n => Some(sum(&values)? as f64 / n as f64),
+                })
+            }
+        };
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod test_query {
+    use crate::d2::query::{Answer, Query};
+    use crate::d2::{load_data, Game, Palette};
+
+    fn run(query: &str) -> Answer {
+        let palette = Palette::rgb();
+        let games: Vec<Game> = load_data(String::from("data/d2/test_p1.txt"), &palette).unwrap();
+        Query::parse(query).unwrap().run(&games, &palette).unwrap()
+    }
+
+    #[test]
+    pub fn test_query_aggregates() {
+        assert_eq!(run("| sum number"), Answer::Value(Some(15)));
+        assert_eq!(
+            run("max.red <= 12 and max.green <= 13 and max.blue <= 14 | sum number"),
+            Answer::Value(Some(8))
+        );
+        assert_eq!(run("| sum power"), Answer::Value(Some(2286)));
+        assert_eq!(run("| mean power"), Answer::Mean(Some(457.2)));
+        assert_eq!(run("sets >= 3 | count"), Answer::Count(4));
+        assert_eq!(run("not (max.red > 12) | count"), Answer::Count(3));
+        assert_eq!(
+            run("max.red > 12 | list"),
+            Answer::List(vec![(3, 3), (4, 4)])
+        );
+        assert_eq!(
+            run("max.red * 2 > 30 or number == 1 | list max.red"),
+            Answer::List(vec![(1, 4), (3, 20)])
+        );
+        assert_eq!(run("total.red - -1 == 6 | count"), Answer::Count(1));
+        assert_eq!(run("number > 10 | max power"), Answer::Value(None));
+        assert_eq!(run("max.yellow > 0 | count"), Answer::Count(0));
+    }
+
+    #[test]
+    pub fn test_query_games() {
+        let answer = run("number == 1");
+        assert_eq!(
+            answer.to_string(),
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green\n"
+        );
+        match run("") {
+            Answer::Games(games) => assert_eq!(games.len(), 5),
+            answer => panic!("{answer:?}"),
+        }
+    }
+
+    #[test]
+    pub fn test_query_overflow() {
+        let palette = Palette::rgb();
+        let games: Vec<Game> = load_data(String::from("data/d2/test_p1.txt"), &palette).unwrap();
+        let run = |query: &str| Query::parse(query).unwrap().run(&games, &palette);
+        let err = run("max.red * 9999999999 * 9999999999 > 0").unwrap_err();
+        assert_eq!(err, "Overflow in 39999999996 * 9999999999 for game 1");
+        assert!(run("0 - 9223372036854775807 - 2 < 0").is_err());
+        assert!(run("| sum number * 4611686018427387904").is_err());
+        assert!(run("| mean 4611686018427387904 + number * 1000000000000000000").is_err());
+        let max = u32::MAX;
+        let huge: Vec<Game> = vec![format!("Game 1: {max} red, {max} green, {max} blue")
+            .parse()
+            .unwrap()];
+        let run_huge = |query: &str| Query::parse(query).unwrap().run(&huge, &palette);
+        assert_eq!(
+            run_huge("power > 0 | count").unwrap_err(),
+            "Overflow in power for game 1"
+        );
+        assert_eq!(run_huge("total.red > 0 | count"), Ok(Answer::Count(1)));
+        // short circuits skip the overflowing side
+        assert_eq!(
+            run("number < 0 and 9223372036854775807 + 1 > 0 | count"),
+            Ok(Answer::Count(0))
+        );
+    }
+
+    #[test]
+    pub fn test_query_errors() {
+        assert!(Query::parse("max.red >").is_err());
+        assert!(Query::parse("foo > 1").is_err());
+        assert!(Query::parse("red.max > 1").is_err());
+        assert!(Query::parse("| average number").is_err());
+        assert!(Query::parse("(number > 1").is_err());
+        assert!(Query::parse("number 3").is_err());
+        assert!(Query::parse("number = 3").is_err());
+        assert!(Query::parse("| count number").is_err());
+    }
+}
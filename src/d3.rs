@@ -1,206 +1,1222 @@
-use std::collections::HashSet;
-use std::fs;
-use std::path::PathBuf;
+pub use crate::grid::Coord;
+use crate::grid::Grid;
+use crate::input;
 
-// static SYMBOLS: &'static str = &"+-*/=%@$#&";
-#[allow(unused)]
+/// a number in the schematic
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-struct PartNumber {
-    value: i32, // encode as negative number for markers
-    loc: Coord,
-    len: i32,
+pub struct Number {
+    pub value: u32,
+    /// location of the first digit
+    pub loc: Coord,
+    /// number of digits
+    pub len: i32,
 }
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Coord {
-    x: i32,
-    y: i32,
+
+impl Number {
+    /// the cells covered by the digits
+    pub fn cells(self) -> impl Iterator<Item = Coord> {
+        (0..self.len).map(move |dx| self.loc + Coord::RIGHT * dx)
+    }
+
+    /// whether the symbol touches the number, also diagonally
+    pub fn is_neighbour(&self, symbol: &Symbol) -> bool {
+        self.cells().any(|cell| cell.chebyshev(symbol.loc) <= 1)
+    }
+}
+
+/// any character other than a digit or `.` in the schematic
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    pub c: char,
+    pub loc: Coord,
+}
+
+/// a number and the symbols touching it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adjacency<'a> {
+    pub number: &'a Number,
+    pub symbols: Vec<&'a Symbol>,
 }
-impl Coord {
-    pub fn new(x: i32, y: i32) -> Self {
-        Coord { x: x, y: y }
+
+/// how many numbers a gear must touch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GearCount {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl GearCount {
+    /// `2` for exactly two numbers, `2+` for two or more. a gear touches at
+    /// least one number, so 0 is rejected
+    pub fn parse(count: &str) -> Option<Self> {
+        let (count, at_least) = match count.strip_suffix('+') {
+            Some(count) => (count, true),
+            None => (count, false),
+        };
+        match (count.parse().ok()?, at_least) {
+            (0, _) => None,
+            (count, true) => Some(GearCount::AtLeast(count)),
+            (count, false) => Some(GearCount::Exactly(count)),
+        }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            GearCount::Exactly(n) => count == *n,
+            GearCount::AtLeast(n) => count >= *n,
+        }
     }
 }
-#[allow(unused)]
-impl PartNumber {
-    pub fn is_neighbour(&self, other: &PartNumber) -> bool {
-        let xmin = self.loc.x - self.len;
-        let xmax = self.loc.x + 1;
 
-        let ymin = self.loc.y - 1;
-        let ymax = self.loc.y + 1;
+/// how the numbers touching a gear are combined into its ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GearOp {
+    Product,
+    Sum,
+    Min,
+    Max,
+}
 
-        if (ymin..=ymax).contains(&other.loc.y) {
-            return (xmin..=xmax).contains(&other.loc.x);
+impl GearOp {
+    pub fn parse(op: &str) -> Option<Self> {
+        match op {
+            "product" => Some(GearOp::Product),
+            "sum" => Some(GearOp::Sum),
+            "min" => Some(GearOp::Min),
+            "max" => Some(GearOp::Max),
+            _ => None,
         }
-        return false;
     }
+
+    /// the combined values, `None` if they overflow u64
+    pub fn apply(&self, mut values: impl Iterator<Item = u64>) -> Option<u64> {
+        match self {
+            GearOp::Product => values.try_fold(1u64, |acc, v| acc.checked_mul(v)),
+            GearOp::Sum => values.try_fold(0u64, |acc, v| acc.checked_add(v)),
+            GearOp::Min => Some(values.min().unwrap_or(0)),
+            GearOp::Max => Some(values.max().unwrap_or(0)),
+        }
+    }
+}
+
+/// what makes a symbol a gear and how its ratio is found. the default is
+/// the puzzle definition, a `*` touching exactly two numbers multiplied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GearRule {
+    pub symbol: char,
+    pub count: GearCount,
+    pub op: GearOp,
+}
+
+impl Default for GearRule {
+    fn default() -> Self {
+        GearRule {
+            symbol: '*',
+            count: GearCount::Exactly(2),
+            op: GearOp::Product,
+        }
+    }
+}
+
+/// a symbol matching the gear character and the numbers touching it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate<'a> {
+    pub symbol: &'a Symbol,
+    pub numbers: Vec<&'a Number>,
+    /// whether the number count satisfies the rule
+    pub qualified: bool,
+    /// the combined numbers, 0 if not qualified and `None` if they overflow
+    /// u64
+    pub ratio: Option<u64>,
+}
+
+/// output format of a rendered schematic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderFormat {
+    /// colored text for a terminal
+    Ansi,
+    /// a standalone html page
+    Html,
 }
 
-#[allow(unused_assignments)]
-pub fn part1(file_path: String) -> u32 {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
+impl RenderFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "ansi" => Some(RenderFormat::Ansi),
+            "html" => Some(RenderFormat::Html),
+            _ => None,
+        }
+    }
+}
 
-    let mut parts = Vec::<PartNumber>::new();
-    let mut symbols = Vec::<PartNumber>::new();
-    let mut y = 0i32;
-    fs::read_to_string(path.as_path())
-        .unwrap()
-        .lines()
-        .for_each(|line| {
-            let mut val = String::new();
-            let mut val_len = 0usize;
-            line.char_indices().for_each(|(x, c)| match c {
-                '0'..='9' => {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub format: RenderFormat,
+    /// add row numbers and column rulers
+    pub rulers: bool,
+    /// the rule deciding which symbols are drawn as gears
+    pub rule: GearRule,
+}
+
+/// how a cell is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    Plain,
+    /// a number touching a symbol, counted in part 1
+    Counted,
+    /// a number touching no symbol
+    Ignored,
+    Symbol,
+    Gear,
+}
+
+impl Highlight {
+    fn open(&self, format: RenderFormat) -> &'static str {
+        match (format, self) {
+            (_, Highlight::Plain) => "",
+            (RenderFormat::Ansi, Highlight::Counted) => "\x1b[32m",
+            (RenderFormat::Ansi, Highlight::Ignored) => "\x1b[31m",
+            (RenderFormat::Ansi, Highlight::Symbol) => "\x1b[33m",
+            (RenderFormat::Ansi, Highlight::Gear) => "\x1b[1;35m",
+            (RenderFormat::Html, Highlight::Counted) => "<span class=\"counted\">",
+            (RenderFormat::Html, Highlight::Ignored) => "<span class=\"ignored\">",
+            (RenderFormat::Html, Highlight::Symbol) => "<span class=\"symbol\">",
+            (RenderFormat::Html, Highlight::Gear) => "<span class=\"gear\">",
+        }
+    }
+
+    fn close(&self, format: RenderFormat) -> &'static str {
+        match (format, self) {
+            (_, Highlight::Plain) => "",
+            (RenderFormat::Ansi, _) => "\x1b[0m",
+            (RenderFormat::Html, _) => "</span>",
+        }
+    }
+}
+
+const HTML_HEADER: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Engine schematic</title>
+<style>
+body { background: #10101a; color: #666; }
+.counted { color: #3c3; }
+.ignored { color: #e44; }
+.symbol { color: #ec3; }
+.gear { color: #f4f; font-weight: bold; }
+</style>
+</head>
+<body>
+<p>
+<span class=\"counted\">counted</span>
+<span class=\"ignored\">ignored</span>
+<span class=\"symbol\">symbol</span>
+<span class=\"gear\">gear</span>
+</p>
+<pre>
+";
+
+const HTML_FOOTER: &str = "</pre>
+</body>
+</html>
+";
+
+/// bit marking a symbol in the cell index, the lower bits hold the index + 1
+const SYMBOL_BIT: u32 = 1 << 31;
+
+/// the numbers and symbols of an engine schematic
+#[derive(Debug, Clone, Default)]
+pub struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    /// the item covering each cell. 0 for an empty cell, the number index + 1
+    /// for a number and the symbol index + 1 with [`SYMBOL_BIT`] set for a
+    /// symbol
+    cells: Grid<u32>,
+    /// the part 1 sum, kept up to date by the edits
+    counted: u64,
+    /// the part 2 sum with the default gear rule, kept up to date by the edits.
+    /// each ratio fits u64, their sum may not
+    gears: u128,
+}
+
+impl Schematic {
+    /// scan the schematic line by line, a number ends at the first non-digit
+    /// and any character other than a digit or `.` is a symbol. fails on a
+    /// number that does not fit u32
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut schematic = Schematic::default();
+        let (mut width, mut height) = (0, 0);
+        for (y, line) in content.lines().enumerate() {
+            let mut val = 0u32;
+            let mut val_len = 0i32;
+            for (x, c) in line.char_indices() {
+                if let Some(digit) = c.to_digit(10) {
                     // store values for later
-                    val.push(c);
+                    val = match val.checked_mul(10).and_then(|v| v.checked_add(digit)) {
+                        Some(val) => val,
+                        None => {
+                            let start = x - val_len as usize;
+                            let digits: String = line[start..]
+                                .chars()
+                                .take_while(char::is_ascii_digit)
+                                .collect();
+                            return Err(format!("Line {}: {digits} does not fit a number", y + 1));
+                        }
+                    };
                     val_len += 1;
+                    continue;
                 }
-                '.' | '\n' => {
-                    // regular part number break, check if any value in temporary to store
-                    if val_len > 0 {
-                        parts.push(PartNumber {
-                            value: val.parse().unwrap(),
-                            loc: Coord::new(x as i32 - 1, y as i32),
-                            len: val_len as i32,
-                        });
-                        val_len = 0;
-                        val.clear();
-                    }
+                // number break, check if any value in temporary to store
+                if val_len > 0 {
+                    schematic.numbers.push(Number {
+                        value: val,
+                        loc: Coord::new(x as i32 - val_len, y as i32),
+                        len: val_len,
+                    });
+                    val = 0;
+                    val_len = 0;
                 }
-                _ => {
-                    // as symbol found, check if previous part number exist to store and store symbol
-                    if val_len > 0 {
-                        parts.push(PartNumber {
-                            value: val.parse().unwrap(),
-                            loc: Coord::new(x as i32 - 1, y as i32),
-                            len: val_len as i32,
-                        });
-                        val_len = 0;
-                        val.clear();
-                    }
-                    symbols.push(PartNumber {
-                        value: -1,
+                if c != '.' {
+                    schematic.symbols.push(Symbol {
+                        c,
                         loc: Coord::new(x as i32, y as i32),
-                        len: 1,
                     });
                 }
-            });
+            }
             if val_len > 0 {
-                parts.push(PartNumber {
-                    value: val.parse().unwrap(),
-                    loc: Coord::new(line.len() as i32 - 1, y as i32),
-                    len: val_len as i32,
+                schematic.numbers.push(Number {
+                    value: val,
+                    loc: Coord::new(line.len() as i32 - val_len, y as i32),
+                    len: val_len,
                 });
-                val_len = 0;
-                val.clear();
-            }
-            y += 1;
-        });
-    let mut unique = HashSet::<PartNumber>::new();
-    parts.into_iter().for_each(|part| {
-        // println!("{:?}", part);
-        symbols.iter().for_each(|symbol| {
-            if part.is_neighbour(symbol) {
-                unique.insert(part);
-            }
-        });
-    });
-    let mut sum = 0u32;
-    unique.into_iter().for_each(|upart| {
-        sum += upart.value as u32;
-    });
+            }
+            width = width.max(line.len() as i32);
+            height = y as i32 + 1;
+        }
+        schematic.build_index(width, height);
+        schematic.counted = schematic.count_part_numbers();
+        schematic.gears = schematic
+            .candidates(&GearRule::default())
+            .iter()
+            .map(puzzle_ratio)
+            .sum();
+        Ok(schematic)
+    }
 
-    return sum;
-}
-
-#[allow(unused_assignments)]
-pub fn part2(file_path: String) -> u32 {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
-
-    let mut parts = Vec::<PartNumber>::new();
-    let mut symbols = Vec::<PartNumber>::new();
-    let mut y = 0i32;
-    fs::read_to_string(path.as_path())
-        .unwrap()
-        .lines()
-        .for_each(|line| {
-            let mut val = String::new();
-            let mut val_len = 0usize;
-            line.char_indices().for_each(|(x, c)| match c {
-                '0'..='9' => {
-                    // store values for later
-                    val.push(c);
-                    val_len += 1;
+    pub fn load(file_path: String) -> Result<Self, String> {
+        let content = input::read(&file_path).map_err(|err| err.to_string())?;
+        Schematic::parse(&content)
+    }
+
+    /// the numbers in reading order, edits may reorder them
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// mark the cells covered by each number and symbol
+    fn build_index(&mut self, width: i32, height: i32) {
+        self.cells = Grid::new(width, height, 0);
+        for (ii, number) in self.numbers.iter().enumerate() {
+            for cell in number.cells() {
+                self.cells[cell] = ii as u32 + 1;
+            }
+        }
+        for (ii, symbol) in self.symbols.iter().enumerate() {
+            self.cells[symbol.loc] = (ii as u32 + 1) | SYMBOL_BIT;
+        }
+    }
+
+    /// the index entries of the cells in the ring around a span of `len`
+    /// cells of a row, skipping cells outside the schematic
+    fn ring(&self, start: Coord, len: i32) -> impl Iterator<Item = u32> + '_ {
+        (-1..=1)
+            .flat_map(move |dy| (-1..=len).map(move |dx| start + Coord::new(dx, dy)))
+            .filter(move |c| c.y != start.y || c.x < start.x || c.x >= start.x + len)
+            .filter_map(|c| self.cells.get(c).copied())
+    }
+
+    /// indices of the symbols touching a number
+    fn symbols_around(&self, number: &Number) -> Vec<usize> {
+        self.ring(number.loc, number.len)
+            .filter(|cell| cell & SYMBOL_BIT != 0)
+            .map(|cell| (cell & !SYMBOL_BIT) as usize - 1)
+            .collect()
+    }
+
+    /// indices of the numbers touching a symbol, each number once
+    fn numbers_around(&self, symbol: &Symbol) -> Vec<usize> {
+        let mut numbers = Vec::<usize>::new();
+        self.ring(symbol.loc, 1)
+            .filter(|cell| *cell != 0 && cell & SYMBOL_BIT == 0)
+            .for_each(|cell| {
+                let ii = cell as usize - 1;
+                if !numbers.contains(&ii) {
+                    numbers.push(ii);
                 }
-                '.' | '\n' => {
-                    // regular part number break, check if any value in temporary to store
-                    if val_len > 0 {
-                        parts.push(PartNumber {
-                            value: val.parse().unwrap(),
-                            loc: Coord::new(x as i32 - 1, y as i32),
-                            len: val_len as i32,
-                        });
-                        val_len = 0;
-                        val.clear();
-                    }
+            });
+        numbers
+    }
+
+    /// every number with the symbols touching it, in the order of
+    /// [`Schematic::numbers`]
+    pub fn adjacency(&self) -> Vec<Adjacency<'_>> {
+        self.numbers
+            .iter()
+            .map(|number| Adjacency {
+                number,
+                symbols: self
+                    .symbols_around(number)
+                    .into_iter()
+                    .map(|ii| &self.symbols[ii])
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// the numbers touching more than one symbol, which part 1 counts once
+    pub fn shared_numbers(&self) -> Vec<Adjacency<'_>> {
+        self.adjacency()
+            .into_iter()
+            .filter(|adjacency| adjacency.symbols.len() > 1)
+            .collect()
+    }
+
+    /// the value of a number if it touches a symbol, otherwise 0
+    fn counted_value(&self, number: &Number) -> u64 {
+        match self.symbols_around(number).is_empty() {
+            true => 0,
+            false => number.value as u64,
+        }
+    }
+
+    /// the part 1 sum computed from scratch
+    fn count_part_numbers(&self) -> u64 {
+        self.numbers.iter().map(|n| self.counted_value(n)).sum()
+    }
+
+    /// the sum of all numbers touching at least one symbol, each number once
+    pub fn part1(&self) -> u64 {
+        self.counted
+    }
+
+    /// the numbers touching a symbol and whether they make it a gear
+    fn candidate<'a>(&'a self, symbol: &'a Symbol, rule: &GearRule) -> Candidate<'a> {
+        let numbers: Vec<&Number> = self
+            .numbers_around(symbol)
+            .into_iter()
+            .map(|ii| &self.numbers[ii])
+            .collect();
+        let qualified = rule.count.accepts(numbers.len());
+        let ratio = match qualified {
+            true => rule.op.apply(numbers.iter().map(|n| n.value as u64)),
+            false => Some(0),
+        };
+        Candidate {
+            symbol,
+            numbers,
+            qualified,
+            ratio,
+        }
+    }
+
+    /// every symbol with the gear character, qualified or not
+    pub fn candidates(&self, rule: &GearRule) -> Vec<Candidate<'_>> {
+        self.symbols
+            .iter()
+            .filter(|symbol| symbol.c == rule.symbol)
+            .map(|symbol| self.candidate(symbol, rule))
+            .collect()
+    }
+
+    /// the sum of the ratios of all gears, `None` if it overflows u64
+    pub fn gear_ratios(&self, rule: &GearRule) -> Option<u64> {
+        sum_ratios(&self.candidates(rule))
+    }
+
+    /// the sum of the gear ratios by the puzzle definition
+    pub fn part2(&self) -> u128 {
+        self.gears
+    }
+
+    /// the character drawn at a cell, `None` outside the schematic
+    pub fn char_at(&self, coord: Coord) -> Option<char> {
+        let cell = *self.cells.get(coord)?;
+        Some(match cell {
+            0 => '.',
+            _ if cell & SYMBOL_BIT != 0 => self.symbols[(cell & !SYMBOL_BIT) as usize - 1].c,
+            _ => {
+                let number = &self.numbers[cell as usize - 1];
+                let digits = format!("{:0width$}", number.value, width = number.len as usize);
+                digits.as_bytes()[(coord.x - number.loc.x) as usize] as char
+            }
+        })
+    }
+
+    /// the part 1 values of the numbers touching the 3x3 box around a cell
+    /// and the part 2 ratios of the symbols in `area`. these are the only
+    /// terms of the sums an edit of the cell can change.
+    fn local_sums(&self, coord: Coord, area: (Coord, Coord)) -> (u64, u128) {
+        let mut numbers = Vec::<u32>::new();
+        for cell in coord.neighbours8().chain([coord]) {
+            match self.cells.get(cell) {
+                Some(&ii) if ii != 0 && ii & SYMBOL_BIT == 0 && !numbers.contains(&ii) => {
+                    numbers.push(ii)
                 }
-                _ => {
-                    // as symbol found, check if previous part number exist to store and store symbol
-                    if val_len > 0 {
-                        parts.push(PartNumber {
-                            value: val.parse().unwrap(),
-                            loc: Coord::new(x as i32 - 1, y as i32),
-                            len: val_len as i32,
-                        });
-                        val_len = 0;
-                        val.clear();
-                    }
-                    if c == '*' {
-                        // only keep track of gears
-                        symbols.push(PartNumber {
-                            value: -1,
-                            loc: Coord::new(x as i32, y as i32),
-                            len: 1,
-                        });
+                _ => (),
+            }
+        }
+        let counted = numbers
+            .iter()
+            .map(|ii| self.counted_value(&self.numbers[*ii as usize - 1]))
+            .sum();
+        let rule = GearRule::default();
+        let mut gears = 0;
+        for y in area.0.y..=area.1.y {
+            for x in area.0.x..=area.1.x {
+                match self.cells.get(Coord::new(x, y)) {
+                    Some(&ii) if ii & SYMBOL_BIT != 0 => {
+                        let symbol = &self.symbols[(ii & !SYMBOL_BIT) as usize - 1];
+                        if symbol.c == rule.symbol {
+                            gears += puzzle_ratio(&self.candidate(symbol, &rule));
+                        }
                     }
+                    _ => (),
                 }
-            });
-            if val_len > 0 {
-                parts.push(PartNumber {
-                    value: val.parse().unwrap(),
-                    loc: Coord::new(line.len() as i32 - 1, y as i32),
-                    len: val_len as i32,
-                });
-                val_len = 0;
-                val.clear();
-            }
-            y += 1;
-        });
-    let mut sum = 0u32;
-    symbols.into_iter().for_each(|symbol| {
-        let mut gears = Vec::<i32>::new();
-        parts.iter().for_each(|part| {
-            if part.is_neighbour(&symbol) {
-                gears.push(part.value);
-            }
-        });
-        if gears.len() > 1 {
-            // gear ration requires at least 2 gears
-            let mut gearing: u32 = gears.pop().unwrap() as u32;
-            while let Some(gear) = gears.pop() {
-                gearing = gearing * gear as u32;
-            }
-            sum += gearing;
+            }
+        }
+        (counted, gears)
+    }
+
+    /// the number covering a cell
+    fn number_at(&self, coord: Coord) -> Option<usize> {
+        match self.cells.get(coord) {
+            Some(&ii) if ii != 0 && ii & SYMBOL_BIT == 0 => Some(ii as usize - 1),
+            _ => None,
+        }
+    }
+
+    /// the digits from a cell onwards in a direction, in reading order
+    fn digit_run(&self, mut cell: Coord, dir: Coord) -> String {
+        let mut digits = Vec::<char>::new();
+        while let Some(d) = self.char_at(cell).filter(char::is_ascii_digit) {
+            digits.push(d);
+            cell = cell + dir;
+        }
+        if dir.x < 0 {
+            digits.reverse();
+        }
+        digits.into_iter().collect()
+    }
+
+    /// remove a number or symbol and clear its cells, the last item of its
+    /// list takes over the index
+    fn remove_item(&mut self, cell: u32) {
+        let ii = (cell & !SYMBOL_BIT) as usize - 1;
+        if cell & SYMBOL_BIT != 0 {
+            let symbol = self.symbols.swap_remove(ii);
+            self.cells[symbol.loc] = 0;
+            if let Some(moved) = self.symbols.get(ii) {
+                self.cells[moved.loc] = cell;
+            }
+        } else {
+            let number = self.numbers.swap_remove(ii);
+            number.cells().for_each(|c| self.cells[c] = 0);
+            if let Some(moved) = self.numbers.get(ii) {
+                moved.cells().for_each(|c| self.cells[c] = cell);
+            }
+        }
+    }
+
+    fn push_number(&mut self, loc: Coord, digits: &str) {
+        let number = Number {
+            value: digits.parse().unwrap(),
+            loc,
+            len: digits.len() as i32,
+        };
+        let cell = self.numbers.len() as u32 + 1;
+        number.cells().for_each(|c| self.cells[c] = cell);
+        self.numbers.push(number);
+    }
+
+    /// change the character of a cell, splitting or joining the numbers of
+    /// its row as needed. the part 1 and part 2 sums are updated from the
+    /// cells around the edit only.
+    pub fn set(&mut self, coord: Coord, c: char) -> Result<(), String> {
+        let old = match self.char_at(coord) {
+            Some(old) => old,
+            None => return Err(format!("{coord:?} is outside the schematic")),
+        };
+        if c.is_control() || !c.is_ascii() {
+            return Err(format!("{c:?} is not a schematic character"));
         }
+        if c == old {
+            return Ok(());
+        }
+        let left = self.digit_run(coord + Coord::LEFT, Coord::LEFT);
+        let right = self.digit_run(coord + Coord::RIGHT, Coord::RIGHT);
+        let start = coord - Coord::RIGHT * left.len() as i32;
+        let joined = format!("{left}{c}{right}");
+        if c.is_ascii_digit() && joined.parse::<u32>().is_err() {
+            return Err(format!("{joined} at {start:?} does not fit a number"));
+        }
+
+        // the changed numbers lie in the span of the digits around the edit,
+        // so every gear they touch is within one cell of that span
+        let area = (
+            start + Coord::new(-1, -1),
+            coord + Coord::new(right.len() as i32 + 1, 1),
+        );
+        let (counted, gears) = self.local_sums(coord, area);
+
+        let mut removed = Vec::<u32>::new();
+        for cell in [coord + Coord::LEFT, coord, coord + Coord::RIGHT] {
+            if let Some(ii) = self.number_at(cell) {
+                removed.push(ii as u32 + 1);
+            }
+        }
+        if self.cells[coord] & SYMBOL_BIT != 0 {
+            removed.push(self.cells[coord]);
+        }
+        // largest index first so the pending indices stay valid
+        removed.sort_unstable_by_key(|cell| std::cmp::Reverse((cell & !SYMBOL_BIT, *cell)));
+        removed.dedup();
+        removed.into_iter().for_each(|cell| self.remove_item(cell));
+
+        if c.is_ascii_digit() {
+            self.push_number(start, &joined);
+        } else {
+            if !left.is_empty() {
+                self.push_number(start, &left);
+            }
+            if !right.is_empty() {
+                self.push_number(coord + Coord::RIGHT, &right);
+            }
+            if c != '.' {
+                self.cells[coord] = (self.symbols.len() as u32 + 1) | SYMBOL_BIT;
+                self.symbols.push(Symbol { c, loc: coord });
+            }
+        }
+
+        let (new_counted, new_gears) = self.local_sums(coord, area);
+        self.counted = self.counted - counted + new_counted;
+        self.gears = self.gears - gears + new_gears;
+        Ok(())
+    }
+
+    /// write the digits of a number starting at a cell, joining any digits
+    /// already next to it. nothing changes if the joined number does not fit.
+    pub fn insert_number(&mut self, loc: Coord, value: u32) -> Result<(), String> {
+        let digits = value.to_string();
+        let end = loc + Coord::RIGHT * digits.len() as i32;
+        if !self.cells.contains(end + Coord::LEFT) {
+            return Err(format!("{value} at {loc:?} does not fit the schematic"));
+        }
+        let left = self.digit_run(loc + Coord::LEFT, Coord::LEFT);
+        let joined = format!("{left}{digits}{}", self.digit_run(end, Coord::RIGHT));
+        if joined.parse::<u32>().is_err() {
+            return Err(format!("{joined} does not fit a number"));
+        }
+        // clearing first means every step only grows a prefix of the joined
+        // number, so no step can fail
+        let cells: Vec<Coord> = (0..digits.len() as i32)
+            .map(|dx| loc + Coord::RIGHT * dx)
+            .collect();
+        for cell in &cells {
+            self.set(*cell, '.')?;
+        }
+        for (cell, d) in cells.into_iter().zip(digits.chars()) {
+            self.set(cell, d)?;
+        }
+        Ok(())
+    }
+
+    /// clear all digits of the number covering a cell
+    pub fn delete_number(&mut self, coord: Coord) -> Result<(), String> {
+        let number = match self.number_at(coord) {
+            Some(ii) => self.numbers[ii],
+            None => return Err(format!("no number at {coord:?}")),
+        };
+        number.cells().try_for_each(|cell| self.set(cell, '.'))
+    }
+
+    pub fn insert_symbol(&mut self, coord: Coord, c: char) -> Result<(), String> {
+        if c == '.' || c.is_ascii_digit() {
+            return Err(format!("{c:?} is not a symbol"));
+        }
+        self.set(coord, c)
+    }
+
+    pub fn delete_symbol(&mut self, coord: Coord) -> Result<(), String> {
+        match self.cells.get(coord) {
+            Some(cell) if cell & SYMBOL_BIT != 0 => self.set(coord, '.'),
+            _ => Err(format!("no symbol at {coord:?}")),
+        }
+    }
+
+    /// the character and highlight of every cell
+    fn annotated_cells(&self, rule: &GearRule) -> Grid<(char, Highlight)> {
+        let (width, height) = (self.cells.width(), self.cells.height());
+        let mut cells = Grid::new(width, height, ('.', Highlight::Plain));
+        for adjacency in self.adjacency() {
+            let number = adjacency.number;
+            let highlight = match adjacency.symbols.is_empty() {
+                true => Highlight::Ignored,
+                false => Highlight::Counted,
+            };
+            // zero padding restores any leading zeros of the number
+            let digits = format!("{:0width$}", number.value, width = number.len as usize);
+            for (cell, c) in number.cells().zip(digits.chars()) {
+                cells[cell] = (c, highlight);
+            }
+        }
+        for symbol in &self.symbols {
+            cells[symbol.loc] = (symbol.c, Highlight::Symbol);
+        }
+        for candidate in self.candidates(rule) {
+            if candidate.qualified {
+                cells[candidate.symbol.loc].1 = Highlight::Gear;
+            }
+        }
+        cells
+    }
+
+    /// draw the schematic with counted and ignored numbers, symbols and gears
+    /// highlighted
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let format = options.format;
+        let cells = self.annotated_cells(&options.rule);
+        let (width, height) = (cells.width(), cells.height());
+        let gutter = (height.max(1) - 1).to_string().len();
+        let mut out = String::new();
+        if format == RenderFormat::Html {
+            out += HTML_HEADER;
+        }
+        if options.rulers {
+            // one line per digit of the column number, most significant first
+            let digits = (width.max(1) - 1).to_string().len() as u32;
+            for d in (0..digits).rev() {
+                out += &" ".repeat(gutter + 1);
+                let scale = 10i32.pow(d);
+                for x in 0..width {
+                    out.push(match d == 0 || x % scale == 0 {
+                        true => char::from(b'0' + (x / scale % 10) as u8),
+                        false => ' ',
+                    });
+                }
+                out.push('\n');
+            }
+        }
+        for y in 0..height {
+            if options.rulers {
+                out += &format!("{y:>gutter$} ");
+            }
+            let mut current = Highlight::Plain;
+            for (c, highlight) in cells.row(y) {
+                if *highlight != current {
+                    out += current.close(format);
+                    out += highlight.open(format);
+                    current = *highlight;
+                }
+                match (format, c) {
+                    (RenderFormat::Html, '<') => out += "&lt;",
+                    (RenderFormat::Html, '>') => out += "&gt;",
+                    (RenderFormat::Html, '&') => out += "&amp;",
+                    _ => out.push(*c),
+                }
+            }
+            out += current.close(format);
+            out.push('\n');
+        }
+        if format == RenderFormat::Html {
+            out += HTML_FOOTER;
+        }
+        out
+    }
+}
+
+pub fn part1(file_path: String) -> u64 {
+    match Schematic::load(file_path) {
+        Ok(schematic) => schematic.part1(),
+        Err(str) => panic!("{str}"),
+    }
+}
+
+pub fn part2(file_path: String) -> u128 {
+    match Schematic::load(file_path) {
+        Ok(schematic) => schematic.part2(),
+        Err(str) => panic!("{str}"),
+    }
+}
+
+/// list each gear candidate with its numbers and whether it qualified
+pub fn gear_report(file_path: String, rule: &GearRule) -> Result<String, String> {
+    let schematic = Schematic::load(file_path)?;
+    let mut report = String::new();
+    let candidates = schematic.candidates(rule);
+    candidates.iter().for_each(|candidate| {
+        let numbers: Vec<String> = candidate
+            .numbers
+            .iter()
+            .map(|n| n.value.to_string())
+            .collect();
+        report += &format!(
+            "'{}' at ({}, {}): [{}] {}\n",
+            candidate.symbol.c,
+            candidate.symbol.loc.x,
+            candidate.symbol.loc.y,
+            numbers.join(", "),
+            match candidate.qualified {
+                true => format!("gear, ratio {}", ratio_str(candidate.ratio)),
+                false => String::from("not a gear"),
+            }
+        );
     });
+    report += &format!(
+        "{} of {} candidate(s) qualified, sum {}\n",
+        candidates.iter().filter(|c| c.qualified).count(),
+        candidates.len(),
+        ratio_str(sum_ratios(&candidates))
+    );
+    Ok(report)
+}
+
+/// the ratio of a candidate by the default rule, two u32 multiplied always
+/// fit u64
+fn puzzle_ratio(candidate: &Candidate) -> u128 {
+    candidate.ratio.map_or(0, u128::from)
+}
+
+/// the sum of the ratios of the candidates, `None` if any or the sum
+/// overflows u64
+fn sum_ratios(candidates: &[Candidate]) -> Option<u64> {
+    candidates
+        .iter()
+        .try_fold(0u64, |acc, c| acc.checked_add(c.ratio?))
+}
 
-    return sum;
+fn ratio_str(ratio: Option<u64>) -> String {
+    match ratio {
+        Some(ratio) => ratio.to_string(),
+        None => String::from("overflow"),
+    }
+}
+
+/// list the numbers touching more than one symbol
+pub fn shared_report(file_path: String) -> Result<String, String> {
+    let schematic = Schematic::load(file_path)?;
+    let mut report = String::new();
+    schematic.shared_numbers().iter().for_each(|adjacency| {
+        let symbols: Vec<String> = adjacency
+            .symbols
+            .iter()
+            .map(|s| format!("'{}' at ({}, {})", s.c, s.loc.x, s.loc.y))
+            .collect();
+        report += &format!(
+            "{} at ({}, {}) touches {}\n",
+            adjacency.number.value,
+            adjacency.number.loc.x,
+            adjacency.number.loc.y,
+            symbols.join(", ")
+        );
+    });
+    Ok(report)
 }
 
 #[cfg(test)]
 mod test_d3 {
+    use crate::d3::{Coord, GearCount, GearOp, GearRule, RenderFormat, RenderOptions, Schematic};
+    use crate::rng::Rng;
+    use std::time::Instant;
+
+    /// a random schematic with numbers of 1 to 3 digits and some symbols
+    fn generate(width: usize, height: usize, seed: u64) -> String {
+        let mut rng = Rng::new(seed);
+        let mut content = String::with_capacity((width + 1) * height);
+        for _ in 0..height {
+            let mut x = 0;
+            while x < width {
+                match rng.next(100) {
+                    0..=9 => {
+                        let len = (1 + rng.next(3) as usize).min(width - x);
+                        for _ in 0..len {
+                            content.push(char::from(b'0' + rng.next(10) as u8));
+                        }
+                        x += len;
+                        continue;
+                    }
+                    10..=13 => content.push(b"*#+$/=%@&-"[rng.next(10) as usize] as char),
+                    _ => content.push('.'),
+                }
+                x += 1;
+            }
+            content.push('\n');
+        }
+        content
+    }
+
+    /// part 2 of [`brute_force`], a `*` touching two or more numbers
+    const AT_LEAST_TWO: GearRule = GearRule {
+        symbol: '*',
+        count: GearCount::AtLeast(2),
+        op: GearOp::Product,
+    };
+
+    /// both parts by comparing every number with every symbol
+    fn brute_force(schematic: &Schematic) -> (u64, u64) {
+        let part1 = schematic
+            .numbers
+            .iter()
+            .filter(|number| schematic.symbols.iter().any(|s| number.is_neighbour(s)))
+            .map(|number| number.value as u64)
+            .sum();
+        let mut part2 = 0u64;
+        schematic
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.c == '*')
+            .for_each(|symbol| {
+                let gears: Vec<u64> = schematic
+                    .numbers
+                    .iter()
+                    .filter(|number| number.is_neighbour(symbol))
+                    .map(|number| number.value as u64)
+                    .collect();
+                if gears.len() > 1 {
+                    part2 += gears.iter().product::<u64>();
+                }
+            });
+        (part1, part2)
+    }
+
+    #[test]
+    pub fn test_d3_index() {
+        for seed in 1..20 {
+            let schematic = Schematic::parse(&generate(60, 40, seed)).unwrap();
+            assert_eq!(
+                (
+                    schematic.part1(),
+                    schematic.gear_ratios(&AT_LEAST_TWO).unwrap()
+                ),
+                brute_force(&schematic)
+            );
+        }
+    }
+
+    /// run with `cargo test --release bench_d3 -- --ignored --nocapture`
+    #[test]
+    #[ignore = "benchmark"]
+    pub fn bench_d3_index() {
+        for size in [100, 1_000, 10_000] {
+            let content = generate(size, size, 0x5eed);
+            let start = Instant::now();
+            let schematic = Schematic::parse(&content).unwrap();
+            let parsed = start.elapsed();
+            let p1 = schematic.part1();
+            let part1 = start.elapsed() - parsed;
+            let p2 = schematic.part2();
+            let part2 = start.elapsed() - parsed - part1;
+            println!(
+                "{size}x{size}: {} numbers, {} symbols, parse {parsed:?}, part 1 {part1:?} ({p1}), \
+                 part 2 {part2:?} ({p2})",
+                schematic.numbers.len(),
+                schematic.symbols.len(),
+            );
+            if size <= 1_000 {
+                let start = Instant::now();
+                let p2 = schematic.gear_ratios(&AT_LEAST_TWO).unwrap();
+                assert_eq!(brute_force(&schematic), (p1, p2));
+                println!("{size}x{size}: brute force {:?}", start.elapsed());
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_d3_schematic() {
+        let schematic = Schematic::load(String::from("data/d3/test_p1.txt")).unwrap();
+        assert_eq!(schematic.numbers().len(), 10);
+        assert_eq!(schematic.symbols().len(), 6);
+        let symbols: String = schematic.symbols().iter().map(|s| s.c).collect();
+        assert_eq!(symbols, "*#*+$*");
+        assert_eq!(schematic.numbers()[0].value, 467);
+        assert_eq!(schematic.numbers()[1].len, 3);
+        assert_eq!(schematic.numbers()[1].loc, Coord::new(5, 0));
+
+        // numbers beyond u32, in a line and at its end
+        assert_eq!(
+            Schematic::parse("12345678901*1").unwrap_err(),
+            "Line 1: 12345678901 does not fit a number"
+        );
+        assert!(Schematic::parse("1.\n*4294967296").is_err());
+        assert!(Schematic::parse("4294967295*").is_ok());
+
+        // two gears with the largest ratio, their sum overflows u64
+        let pair = "4294967295*4294967295";
+        let schematic = Schematic::parse(&format!("{pair}\n\n{pair}")).unwrap();
+        let ratio = u32::MAX as u128 * u32::MAX as u128;
+        assert_eq!(schematic.part2(), 2 * ratio);
+        assert_eq!(schematic.gear_ratios(&GearRule::default()), None);
+
+        // numbers at the end of a line and next to symbols
+        let schematic = Schematic::parse("12*34\n.5.#6").unwrap();
+        let values: Vec<u32> = schematic.numbers().iter().map(|n| n.value).collect();
+        assert_eq!(values, vec![12, 34, 5, 6]);
+        assert_eq!(schematic.part1(), 12 + 34 + 5 + 6);
+        // the * touches three numbers, so it is no gear by the puzzle rule
+        assert_eq!(schematic.part2(), 0);
+    }
+
+    #[test]
+    pub fn test_d3_adjacency() {
+        let schematic = Schematic::load(String::from("data/d3/test_p1.txt")).unwrap();
+        let adjacency = schematic.adjacency();
+        assert_eq!(adjacency.len(), 10);
+        // 114 and 58 touch no symbol
+        let lonely: Vec<u32> = adjacency
+            .iter()
+            .filter(|a| a.symbols.is_empty())
+            .map(|a| a.number.value)
+            .collect();
+        assert_eq!(lonely, vec![114, 58]);
+        assert_eq!(adjacency[0].symbols[0].c, '*');
+        assert!(schematic.shared_numbers().is_empty());
+
+        // equal values are still separate numbers, only one of the 5s is shared
+        let schematic = Schematic::parse("*..\n.7#\n5$5").unwrap();
+        let shared = schematic.shared_numbers();
+        let shared: Vec<(u32, i32, String)> = shared
+            .iter()
+            .map(|a| {
+                (
+                    a.number.value,
+                    a.number.loc.x,
+                    a.symbols.iter().map(|s| s.c).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            shared,
+            vec![(7, 1, String::from("*#$")), (5, 2, String::from("#$"))]
+        );
+        assert_eq!(schematic.part1(), 7 + 5 + 5);
+        assert_eq!(schematic.part2(), 0);
+    }
+
+    #[test]
+    pub fn test_d3_gear_rules() {
+        let schematic = Schematic::parse("12*34\n.5.#6\n7#..8").unwrap();
+        let rule = GearRule::default();
+        let candidates = schematic.candidates(&rule);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].numbers.len(), 3);
+        assert!(!candidates[0].qualified);
+
+        let at_least = GearRule {
+            count: GearCount::AtLeast(2),
+            ..rule
+        };
+        assert_eq!(schematic.gear_ratios(&at_least), Some(12 * 34 * 5));
+        let sum = GearRule {
+            op: GearOp::Sum,
+            ..at_least
+        };
+        assert_eq!(schematic.gear_ratios(&sum), Some(12 + 34 + 5));
+        // the # at (3, 1) touches 34, 6 and 8, the one at (1, 2) touches 5 and 7
+        let hash = GearRule {
+            symbol: '#',
+            op: GearOp::Max,
+            ..rule
+        };
+        assert_eq!(schematic.gear_ratios(&hash), Some(7));
+
+        assert_eq!(GearCount::parse("2"), Some(GearCount::Exactly(2)));
+        assert_eq!(GearCount::parse("3+"), Some(GearCount::AtLeast(3)));
+        assert_eq!(GearCount::parse("x"), None);
+        assert_eq!(GearCount::parse("0"), None);
+        assert_eq!(GearCount::parse("0+"), None);
+        assert_eq!(GearCount::parse("1+"), Some(GearCount::AtLeast(1)));
+
+        // three ten digit numbers multiplied overflow u64
+        let large = Schematic::parse(
+            "4000000000*4000000000
+.....*4000000000",
+        )
+        .unwrap();
+        let three = GearRule {
+            count: GearCount::Exactly(3),
+            ..rule
+        };
+        assert_eq!(large.gear_ratios(&three), None);
+        assert_eq!(large.gear_ratios(&rule), Some(16_000_000_000_000_000_000));
+        assert_eq!(GearOp::parse("min"), Some(GearOp::Min));
+
+        let report = super::gear_report(String::from("data/d3/test_p2.txt"), &rule).unwrap();
+        assert!(super::gear_report(String::from("data/d3/missing.txt"), &rule).is_err());
+        assert!(Schematic::load(String::from("data/d3/missing.txt"))
+            .is_err_and(|err| err.starts_with("Could not read")));
+        assert!(report.contains("'*' at (3, 1): [467, 35] gear, ratio 16345"));
+        assert!(report.contains("'*' at (3, 4): [617] not a gear"));
+        assert!(report.ends_with("2 of 3 candidate(s) qualified, sum 467835\n"));
+    }
+
+    #[test]
+    pub fn test_d3_edits() {
+        let mut schematic = Schematic::parse("467..114..\n...*......\n..35..633.").unwrap();
+        assert_eq!((schematic.part1(), schematic.part2()), (502, 16345));
+        // joining 114 with the new digit moves it next to the gear
+        schematic.set(Coord::new(4, 0), '9').unwrap();
+        assert_eq!(schematic.char_at(Coord::new(4, 0)), Some('9'));
+        assert_eq!((schematic.part1(), schematic.part2()), (467 + 9114 + 35, 0));
+        // splitting 467 keeps 46 next to the gear
+        schematic.set(Coord::new(2, 0), '#').unwrap();
+        assert_eq!(schematic.part1(), 46 + 9114 + 35);
+        schematic.delete_symbol(Coord::new(3, 1)).unwrap();
+        assert_eq!((schematic.part1(), schematic.part2()), (46, 0));
+        schematic.insert_symbol(Coord::new(8, 1), '*').unwrap();
+        assert_eq!(
+            (schematic.part1(), schematic.part2()),
+            (46 + 9114 + 633, 9114 * 633)
+        );
+        schematic.delete_number(Coord::new(5, 0)).unwrap();
+        assert_eq!((schematic.part1(), schematic.part2()), (46 + 633, 0));
+        schematic.insert_number(Coord::new(8, 0), 12).unwrap();
+        assert_eq!(
+            (schematic.part1(), schematic.part2()),
+            (46 + 12 + 633, 12 * 633)
+        );
+        assert!(schematic.set(Coord::new(10, 0), '1').is_err());
+        assert!(schematic.insert_number(Coord::new(8, 2), 123).is_err());
+        schematic.insert_number(Coord::new(0, 2), 999999).unwrap();
+        assert!(schematic.set(Coord::new(9, 2), '9').is_err());
+        assert_eq!(schematic.char_at(Coord::new(9, 2)), Some('.'));
+        assert!(schematic.delete_number(Coord::new(0, 1)).is_err());
+        assert!(schematic.insert_symbol(Coord::new(0, 1), '7').is_err());
+
+        // a third gear with the largest ratio between two others
+        let (gear, pair) = ("4294967295*4294967295", "4294967295.4294967295");
+        let content = format!("{gear}\n\n{pair}\n\n{gear}");
+        let mut schematic = Schematic::parse(&content).unwrap();
+        let ratio = u32::MAX as u128 * u32::MAX as u128;
+        assert_eq!(schematic.part2(), 2 * ratio);
+        schematic.insert_symbol(Coord::new(10, 2), '*').unwrap();
+        assert_eq!(schematic.part2(), 3 * ratio);
+        schematic.delete_symbol(Coord::new(10, 0)).unwrap();
+        assert_eq!(schematic.part2(), 2 * ratio);
+
+        let (width, height) = (40, 30);
+        let mut text: Vec<Vec<char>> = generate(width, height, 11)
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        let mut schematic = Schematic::parse(&generate(width, height, 11)).unwrap();
+        let mut rng = Rng::new(12);
+        let mut checked = 0;
+        for step in 0..5000 {
+            let (x, y) = (
+                rng.next(width as u64) as usize,
+                rng.next(height as u64) as usize,
+            );
+            let coord = Coord::new(x as i32, y as i32);
+            match rng.next(5) {
+                0 => {
+                    let c = b"0123456789......*#+"[rng.next(19) as usize] as char;
+                    if schematic.set(coord, c).is_ok() {
+                        text[y][x] = c;
+                    }
+                }
+                1 => {
+                    let value = rng.next(1000) as u32;
+                    if schematic.insert_number(coord, value).is_ok() {
+                        for (ii, d) in value.to_string().chars().enumerate() {
+                            text[y][x + ii] = d;
+                        }
+                    }
+                }
+                2 => {
+                    if let Some(ii) = schematic.number_at(coord) {
+                        let number = schematic.numbers[ii];
+                        schematic.delete_number(coord).unwrap();
+                        number.cells().for_each(|c| text[y][c.x as usize] = '.');
+                    }
+                }
+                3 => {
+                    if schematic.insert_symbol(coord, '*').is_ok() {
+                        text[y][x] = '*';
+                    }
+                }
+                _ => {
+                    if schematic.delete_symbol(coord).is_ok() {
+                        text[y][x] = '.';
+                    }
+                }
+            }
+            let expected = (
+                schematic.count_part_numbers(),
+                u128::from(schematic.gear_ratios(&GearRule::default()).unwrap()),
+            );
+            assert_eq!(
+                (schematic.part1(), schematic.part2()),
+                expected,
+                "step {step}"
+            );
+            if step % 250 == 0 {
+                let content: Vec<String> = text.iter().map(|l| l.iter().collect()).collect();
+                let fresh = Schematic::parse(&content.join("\n")).unwrap();
+                assert_eq!((fresh.part1(), fresh.part2()), expected, "step {step}");
+                // the inserted numbers can make the products of three or more
+                // numbers overflow, leave those to the comparison above
+                if let Some(at_least_two) = fresh.gear_ratios(&AT_LEAST_TWO) {
+                    checked += 1;
+                    assert_eq!(
+                        brute_force(&fresh),
+                        (expected.0, at_least_two),
+                        "step {step}"
+                    );
+                }
+                for coord in fresh.cells.coords() {
+                    assert_eq!(schematic.char_at(coord), fresh.char_at(coord));
+                }
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    pub fn test_d3_render() {
+        let schematic = Schematic::parse("007*1.\n......\n.&..23").unwrap();
+        let mut options = RenderOptions {
+            format: RenderFormat::Ansi,
+            rulers: false,
+            rule: GearRule::default(),
+        };
+        assert_eq!(
+            schematic.render(&options),
+            "\x1b[32m007\x1b[0m\x1b[1;35m*\x1b[0m\x1b[32m1\x1b[0m.\n......\n\
+             .\x1b[33m&\x1b[0m..\x1b[31m23\x1b[0m\n"
+        );
+
+        options.rulers = true;
+        let plain = Schematic::parse(&".".repeat(12)).unwrap();
+        assert_eq!(
+            plain.render(&options),
+            "  0         1 \n  012345678901\n0 ............\n"
+        );
+
+        options.format = RenderFormat::Html;
+        let html = schematic.render(&options);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("0 <span class=\"counted\">007</span><span class=\"gear\">*</span>"));
+        assert!(Schematic::parse("1&")
+            .unwrap()
+            .render(&options)
+            .contains("&amp;"));
+        assert_eq!(RenderFormat::parse("svg"), None);
+    }
 
     #[test]
     pub fn test_d3_p1() {
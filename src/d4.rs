@@ -1,103 +1,625 @@
-use std::collections::HashSet;
-use std::fs;
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::BufRead;
 
-#[derive(Debug)]
-struct Card {
-    nr: i32,
-    correct: Vec<i32>,
-    multiplier: u32,
+use crate::input;
+use crate::parse::{parse_ints, split_label, ParseError};
+
+/// a rule broken by a line of the scratchcards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardError {
+    /// the scratchcards could not be read
+    Io(String),
+    /// the line is not `Card N: winning | drawn`
+    Format(usize, String),
+    /// a token that is not a number
+    InvalidNumber(usize, String),
+    /// the card number does not follow the previous card
+    Numbering {
+        line: usize,
+        expected: u32,
+        found: u32,
+    },
+    /// a winning number listed more than once on a card
+    DuplicateWinner(usize, u32),
+    /// a card with another number of winning or drawn numbers than the first
+    Columns {
+        line: usize,
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
 }
 
-impl Card {
+impl CardError {
+    /// the line breaking the rule, `None` when nothing could be read
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            CardError::Io(_) => None,
+            CardError::Format(line, _)
+            | CardError::InvalidNumber(line, _)
+            | CardError::DuplicateWinner(line, _) => Some(*line),
+            CardError::Numbering { line, .. } | CardError::Columns { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl fmt::Display for CardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line() {
+            write!(f, "Line {line}: ")?;
+        }
+        match self {
+            CardError::Io(err) => write!(f, "{err}"),
+            CardError::Format(_, reason) => write!(f, "{reason}"),
+            CardError::InvalidNumber(_, token) => write!(f, "invalid number {token:?}"),
+            CardError::Numbering {
+                expected, found, ..
+            } => write!(f, "expected card {expected}, found card {found}"),
+            CardError::DuplicateWinner(_, number) => {
+                write!(f, "winning number {number} listed twice")
+            }
+            CardError::Columns {
+                expected, found, ..
+            } => write!(
+                f,
+                "{} winning and {} drawn numbers, expected {} and {}",
+                found.0, found.1, expected.0, expected.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CardError {}
+
+/// the largest number kept as a bit of a [`NumberSet`]
+pub const MAX_BIT: u32 = 127;
+
+/// a set of card numbers with one bit per number up to [`MAX_BIT`], the
+/// rare larger numbers are kept in a sorted list
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NumberSet {
+    bits: u128,
+    large: Vec<u32>,
+}
+
+impl NumberSet {
     pub fn new() -> Self {
-        Card {
-            nr: 0,
-            correct: Vec::<i32>::new(),
-            multiplier: 1,
+        NumberSet::default()
+    }
+
+    /// add a number, returns false if it was already in the set
+    pub fn insert(&mut self, nbr: u32) -> bool {
+        if nbr > MAX_BIT {
+            return match self.large.binary_search(&nbr) {
+                Ok(_) => false,
+                Err(at) => {
+                    self.large.insert(at, nbr);
+                    true
+                }
+            };
         }
+        let bit = 1u128 << nbr;
+        let added = self.bits & bit == 0;
+        self.bits |= bit;
+        added
     }
 
-    pub fn from_string(line: &str) -> Self {
-        let mut card = Card::new();
-        let (card_str, line) = line.split_once(':').expect("Colon seperator not found");
+    pub fn contains(&self, nbr: u32) -> bool {
+        match nbr > MAX_BIT {
+            true => self.large.binary_search(&nbr).is_ok(),
+            false => self.bits & (1u128 << nbr) != 0,
+        }
+    }
 
-        let (_, card_nr) = card_str.split_once(" ").unwrap();
-        card.nr = card_nr.trim().parse::<i32>().unwrap();
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize + self.large.len()
+    }
 
-        let (win_str, nbr_str) = line.split_once('|').expect("| seperator not found");
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0 && self.large.is_empty()
+    }
 
-        let mut winning = HashSet::<i32>::new();
+    pub fn intersection(&self, other: &NumberSet) -> NumberSet {
+        NumberSet {
+            bits: self.bits & other.bits,
+            large: self
+                .large
+                .iter()
+                .filter(|nbr| other.contains(**nbr))
+                .copied()
+                .collect(),
+        }
+    }
 
-        win_str.trim().split(' ').into_iter().for_each(|nbr| {
-            if let Ok(winning_nbr) = nbr.trim().parse::<i32>() {
-                let _ = winning.insert(winning_nbr);
-            }
+    /// the numbers in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let bits = self.bits;
+        (0..=MAX_BIT)
+            .filter(move |nbr| bits & (1u128 << nbr) != 0)
+            .chain(self.large.iter().copied())
+    }
+}
+
+impl FromIterator<u32> for NumberSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        let mut set = NumberSet::new();
+        iter.into_iter().for_each(|nbr| {
+            set.insert(nbr);
         });
+        set
+    }
+}
 
-        nbr_str.trim().split(' ').into_iter().for_each(|nbr| {
-            if let Ok(draw) = nbr.parse::<i32>() {
-                if winning.contains(&draw) {
-                    card.correct.push(draw);
-                }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    nr: u32,
+    winning: Vec<u32>,
+    drawn: Vec<u32>,
+    winning_set: NumberSet,
+    drawn_set: NumberSet,
+    /// whether a number is drawn more than once, each draw is a match
+    drawn_twice: bool,
+}
+
+/// the numbers of a space separated list
+fn parse_numbers(line: usize, list: &str) -> Result<Vec<u32>, CardError> {
+    parse_ints::<u32>(list).map_err(|err| match err {
+        ParseError::InvalidNumber(token) => CardError::InvalidNumber(line, token),
+        err => CardError::Format(line, err.to_string()),
+    })
+}
+
+impl Card {
+    /// parse `Card N: winning | drawn` found on line `line`, checking the
+    /// card on its own
+    pub fn parse(line: usize, card_str: &str) -> Result<Self, CardError> {
+        let format = |reason: &str| CardError::Format(line, reason.to_string());
+        let (header, numbers) =
+            split_label(card_str).map_err(|_| format("colon separator not found"))?;
+        let nr = match header.split_once("Card") {
+            Some(("", nr)) if nr.starts_with(' ') => nr.trim(),
+            _ => return Err(format("line does not start with `Card N`")),
+        };
+        let nr = nr
+            .parse::<u32>()
+            .map_err(|_| CardError::InvalidNumber(line, nr.to_string()))?;
+        let (win_str, nbr_str) = numbers
+            .split_once('|')
+            .ok_or_else(|| format("| separator not found"))?;
+
+        let winning = parse_numbers(line, win_str)?;
+        let drawn = parse_numbers(line, nbr_str)?;
+        let mut winning_set = NumberSet::new();
+        for number in &winning {
+            if !winning_set.insert(*number) {
+                return Err(CardError::DuplicateWinner(line, *number));
             }
-        });
+        }
+        let mut drawn_set = NumberSet::new();
+        let mut drawn_twice = false;
+        for number in &drawn {
+            drawn_twice |= !drawn_set.insert(*number);
+        }
+        Ok(Card {
+            nr,
+            winning,
+            drawn,
+            winning_set,
+            drawn_set,
+            drawn_twice,
+        })
+    }
 
-        return card;
+    pub fn get_nr(&self) -> u32 {
+        self.nr
     }
-}
 
-pub fn part1(file_path: String) -> u32 {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
+    pub fn get_winning(&self) -> &[u32] {
+        &self.winning
+    }
 
+    pub fn get_drawn(&self) -> &[u32] {
+        &self.drawn
+    }
+
+    /// the drawn numbers that are winning numbers
+    pub fn correct(&self) -> Vec<u32> {
+        self.drawn
+            .iter()
+            .filter(|nbr| self.winning_set.contains(**nbr))
+            .copied()
+            .collect()
+    }
+
+    /// the number of drawn numbers that are winning numbers, a winning
+    /// number drawn twice matches twice
+    pub fn matches(&self) -> usize {
+        match self.drawn_twice {
+            true => self
+                .drawn
+                .iter()
+                .filter(|nbr| self.winning_set.contains(**nbr))
+                .count(),
+            false => self.winning_set.intersection(&self.drawn_set).len(),
+        }
+    }
+}
+
+/// parse every card and check that the cards are numbered from 1 without
+/// gaps and all have as many numbers as the first card. all violations are
+/// reported, not only the first.
+pub fn parse_cards(content: &str) -> Result<Vec<Card>, Vec<CardError>> {
     let mut cards = Vec::<Card>::new();
-    fs::read_to_string(path.as_path())
-        .expect("Could not open file")
-        .lines()
-        .for_each(|line| cards.push(Card::from_string(line)));
+    let mut errors = Vec::<CardError>::new();
+    let mut columns = None;
+    let mut expected: u32 = 1;
+    for (ii, card_str) in content.lines().enumerate() {
+        let line = ii + 1;
+        let card = match Card::parse(line, card_str) {
+            Ok(card) => card,
+            Err(err) => {
+                errors.push(err);
+                expected = expected.saturating_add(1);
+                continue;
+            }
+        };
+        if card.nr != expected {
+            errors.push(CardError::Numbering {
+                line,
+                expected,
+                found: card.nr,
+            });
+        }
+        expected = card.nr.saturating_add(1);
+        let found = (card.winning.len(), card.drawn.len());
+        match columns {
+            None => columns = Some(found),
+            Some(expected) if expected != found => errors.push(CardError::Columns {
+                line,
+                expected,
+                found,
+            }),
+            _ => (),
+        }
+        cards.push(card);
+    }
+    match errors.is_empty() {
+        true => Ok(cards),
+        false => Err(errors),
+    }
+}
 
-    let mut sum = 0u32;
-    cards.into_iter().for_each(|card| {
-        if card.correct.len() >= 1 {
-            sum += 1 << (card.correct.len() - 1);
+pub fn load_cards(file_path: String) -> Result<Vec<Card>, Vec<CardError>> {
+    match input::read(&file_path) {
+        Ok(content) => parse_cards(&content),
+        Err(err) => Err(vec![CardError::Io(err.to_string())]),
+    }
+}
+
+/// the cards of a file, panics with every violation found
+fn read_cards(file_path: String) -> Vec<Card> {
+    match load_cards(file_path) {
+        Ok(cards) => cards,
+        Err(errors) => {
+            let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+            panic!("{}", errors.join("\n"))
         }
-        // println!("{:?}", card);
-    });
+    }
+}
 
-    return sum;
+/// turns the number of matches on each card, in card order, into a score
+pub trait Scoring {
+    /// the name to select the scoring with, see [`parse_scoring`]
+    fn name(&self) -> String;
+
+    /// the score, saturating at `u64::MAX` when it does not fit
+    fn score(&self, matches: &[usize]) -> u64;
 }
 
-pub fn part2(file_path: String) -> u32 {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
+/// part 1, the first match is worth one point and each further match
+/// doubles it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Points;
 
-    let mut cards = Vec::<Card>::new();
-    fs::read_to_string(path.as_path())
-        .expect("Could not open file")
-        .lines()
-        .for_each(|line| cards.push(Card::from_string(line)));
-
-    let mut sum = 0u32;
-    for ii in 0..cards.len() {
-        let wins = cards[ii].correct.len();
-        let upper = ii + wins + 1;
-        let upper = if upper <= cards.len() {
-            upper
-        } else {
-            cards.len()
+impl Scoring for Points {
+    fn name(&self) -> String {
+        String::from("points")
+    }
+
+    fn score(&self, matches: &[usize]) -> u64 {
+        matches
+            .iter()
+            .filter(|m| **m >= 1)
+            .map(|m| {
+                u32::try_from(m - 1)
+                    .ok()
+                    .and_then(|shift| 1u64.checked_shl(shift))
+                    .unwrap_or(u64::MAX)
+            })
+            .fold(0u64, u64::saturating_add)
+    }
+}
+
+/// a fixed number of points per match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinearPoints(pub u64);
+
+impl Scoring for LinearPoints {
+    fn name(&self) -> String {
+        format!("linear:{}", self.0)
+    }
+
+    fn score(&self, matches: &[usize]) -> u64 {
+        matches
+            .iter()
+            .map(|m| (*m as u64).saturating_mul(self.0))
+            .fold(0u64, u64::saturating_add)
+    }
+}
+
+/// the number of cards held when each instance of a card wins copies of the
+/// cards after it, dividing the copies by `divisor` for each card further
+/// away. saturates at `u64::MAX`, as cascades can grow exponentially.
+fn cascade(matches: &[usize], divisor: u64) -> u64 {
+    let mut multiplier = vec![1u64; matches.len()];
+    let mut sum = 0u64;
+    for ii in 0..matches.len() {
+        let upper = (ii + matches[ii] + 1).min(matches.len());
+        let mut won = multiplier[ii];
+        for count in multiplier[ii + 1..upper].iter_mut() {
+            if won == 0 {
+                break;
+            }
+            *count = count.saturating_add(won);
+            won /= divisor;
+        }
+        sum = sum.saturating_add(multiplier[ii]);
+    }
+    sum
+}
+
+/// part 2, each instance of a card with N matches wins a copy of each of the
+/// next N cards. the score is the number of cards held in the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Copies;
+
+impl Scoring for Copies {
+    fn name(&self) -> String {
+        String::from("copies")
+    }
+
+    fn score(&self, matches: &[usize]) -> u64 {
+        stream_copies(matches.iter().copied())
+    }
+}
+
+/// like [`Copies`], but each card further away wins the copies of the card
+/// before it divided by the divisor, stopping when nothing is left
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecayingCopies(pub u64);
+
+impl Scoring for DecayingCopies {
+    fn name(&self) -> String {
+        format!("decay:{}", self.0)
+    }
+
+    fn score(&self, matches: &[usize]) -> u64 {
+        cascade(matches, self.0)
+    }
+}
+
+/// like [`Copies`], but a card with N matches wins copies of the previous N
+/// cards, so the cards are played from the last one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviousCopies;
+
+impl Scoring for PreviousCopies {
+    fn name(&self) -> String {
+        String::from("previous")
+    }
+
+    fn score(&self, matches: &[usize]) -> u64 {
+        let reversed: Vec<usize> = matches.iter().rev().copied().collect();
+        Copies.score(&reversed)
+    }
+}
+
+/// a scoring by name: `points`, `copies`, `linear[:points per match]`,
+/// `decay[:divisor]` or `previous`
+pub fn parse_scoring(spec: &str) -> Option<Box<dyn Scoring>> {
+    let (name, arg) = match spec.split_once(':') {
+        Some((name, arg)) => (name, Some(arg.parse::<u64>().ok()?)),
+        None => (spec, None),
+    };
+    match (name, arg) {
+        ("points", None) => Some(Box::new(Points)),
+        ("copies", None) => Some(Box::new(Copies)),
+        ("previous", None) => Some(Box::new(PreviousCopies)),
+        ("linear", arg) => Some(Box::new(LinearPoints(arg.unwrap_or(1)))),
+        ("decay", arg) if arg != Some(0) => Some(Box::new(DecayingCopies(arg.unwrap_or(2)))),
+        _ => None,
+    }
+}
+
+/// the number of matches on each card
+pub fn matches(cards: &[Card]) -> Vec<usize> {
+    cards.iter().map(|card| card.matches()).collect()
+}
+
+/// part 2 over the matches of one card at a time, keeping only the copies
+/// won for the cards still to come in a ring buffer
+pub fn stream_copies(matches: impl IntoIterator<Item = usize>) -> u64 {
+    let mut pending = VecDeque::<u64>::new();
+    let mut sum = 0u64;
+    for wins in matches {
+        let instances = pending.pop_front().unwrap_or(0).saturating_add(1);
+        for ii in 0..wins {
+            match pending.get_mut(ii) {
+                Some(copies) => *copies = copies.saturating_add(instances),
+                None => pending.push_back(instances),
+            }
+        }
+        sum = sum.saturating_add(instances);
+    }
+    sum
+}
+
+/// part 2 read line by line without keeping the cards, only each card on
+/// its own is checked
+pub fn stream_part2(reader: impl BufRead) -> Result<u64, CardError> {
+    let mut error = None;
+    let sum = stream_copies(reader.lines().enumerate().map_while(|(ii, line)| {
+        let card = match line {
+            Ok(line) => Card::parse(ii + 1, &line),
+            Err(err) => Err(CardError::Io(format!(
+                "Could not read line {}: {err}",
+                ii + 1
+            ))),
         };
-        for jj in ii + 1..upper {
-            cards[jj].multiplier += cards[ii].multiplier;
+        match card {
+            Ok(card) => Some(card.matches()),
+            Err(err) => {
+                error = Some(err);
+                None
+            }
+        }
+    }));
+    match error {
+        Some(err) => Err(err),
+        None => Ok(sum),
+    }
+}
+
+/// copies a card won from one earlier card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contribution {
+    /// the number of the winning card
+    pub from: u32,
+    pub copies: u64,
+}
+
+/// how many instances of a card part 2 ends up with and where they came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CardTrace {
+    pub nr: u32,
+    pub matches: usize,
+    /// the original card plus all copies won
+    pub instances: u64,
+    pub contributions: Vec<Contribution>,
+}
+
+/// follow the part 2 copies card by card, the instances sum to the part 2
+/// answer
+pub fn cascade_trace(cards: &[Card]) -> Vec<CardTrace> {
+    let mut traces: Vec<CardTrace> = cards
+        .iter()
+        .map(|card| CardTrace {
+            nr: card.nr,
+            matches: card.matches(),
+            instances: 1,
+            contributions: Vec::new(),
+        })
+        .collect();
+    for ii in 0..traces.len() {
+        let (from, won) = (traces[ii].nr, traces[ii].instances);
+        let upper = (ii + traces[ii].matches + 1).min(traces.len());
+        for trace in traces[ii + 1..upper].iter_mut() {
+            trace.instances = trace.instances.saturating_add(won);
+            trace.contributions.push(Contribution { from, copies: won });
+        }
+    }
+    traces
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CascadeFormat {
+    Table,
+    Dot,
+}
+
+impl CascadeFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "table" => Some(CascadeFormat::Table),
+            "dot" => Some(CascadeFormat::Dot),
+            _ => None,
+        }
+    }
+}
+
+/// the cascade as a table with one row per card, or as a graphviz graph
+/// with an edge from each winning card to the cards it copied
+pub fn render_cascade(traces: &[CardTrace], format: CascadeFormat) -> String {
+    let mut out = String::new();
+    match format {
+        CascadeFormat::Table => {
+            out += &format!("{:>6} {:>7} {:>10}  from\n", "card", "matches", "instances");
+            for trace in traces {
+                let from: Vec<String> = trace
+                    .contributions
+                    .iter()
+                    .map(|c| format!("{} (+{})", c.from, c.copies))
+                    .collect();
+                out += &format!(
+                    "{:>6} {:>7} {:>10}  {}\n",
+                    trace.nr,
+                    trace.matches,
+                    trace.instances,
+                    match from.is_empty() {
+                        true => String::from("-"),
+                        false => from.join(", "),
+                    }
+                );
+            }
+            let total = traces
+                .iter()
+                .fold(0u64, |sum, t| sum.saturating_add(t.instances));
+            out += &format!("total: {total}\n");
+        }
+        CascadeFormat::Dot => {
+            out += "digraph cascade {\n    node [shape=box];\n";
+            for trace in traces {
+                out += &format!(
+                    "    c{} [label=\"Card {}\\n{} matches, {} instances\"];\n",
+                    trace.nr, trace.nr, trace.matches, trace.instances
+                );
+            }
+            for trace in traces {
+                for c in &trace.contributions {
+                    out += &format!(
+                        "    c{} -> c{} [label=\"{}\"];\n",
+                        c.from, trace.nr, c.copies
+                    );
+                }
+            }
+            out += "}\n";
         }
-        sum += cards[ii].multiplier;
-        // println!("{:?}: +{:?} = {:?}", ii, cards[ii].multiplier, sum);
     }
-    return sum;
+    out
+}
+
+/// score the cards of a file, or every violation found
+pub fn score(file_path: String, scoring: &dyn Scoring) -> Result<u64, Vec<CardError>> {
+    load_cards(file_path).map(|cards| scoring.score(&matches(&cards)))
+}
+
+pub fn part1(file_path: String) -> u64 {
+    Points.score(&matches(&read_cards(file_path)))
+}
+
+pub fn part2(file_path: String) -> u64 {
+    Copies.score(&matches(&read_cards(file_path)))
 }
 
 #[cfg(test)]
 mod test_d4 {
+    use crate::d4::{
+        cascade_trace, load_cards, parse_cards, parse_scoring, render_cascade, score,
+        stream_copies, stream_part2, Card, CardError, CascadeFormat, Contribution, Copies,
+        DecayingCopies, LinearPoints, NumberSet, Points, PreviousCopies, Scoring,
+    };
+    use crate::rng::Rng;
+    use std::time::Instant;
 
     #[test]
     pub fn test_d4_p1() {
@@ -109,4 +631,251 @@ mod test_d4 {
         let a = super::part2(String::from("data/d4/test_p1.txt"));
         assert_eq!(a, 30);
     }
+
+    /// a random card line with 5 winning and 8 drawn distinct numbers below
+    /// 100, few enough matches that the copies do not grow exponentially
+    fn generate_card(rng: &mut Rng, nr: usize) -> String {
+        let mut winning = NumberSet::new();
+        while winning.len() < 5 {
+            winning.insert(rng.next(100) as u32);
+        }
+        let winning: Vec<String> = winning.iter().map(|n| format!("{n:>2}")).collect();
+        let mut drawn = Vec::<String>::new();
+        while drawn.len() < 8 {
+            let nbr = format!("{:>2}", rng.next(100));
+            if !drawn.contains(&nbr) {
+                drawn.push(nbr);
+            }
+        }
+        format!("Card {nr}: {} | {}", winning.join(" "), drawn.join(" "))
+    }
+
+    /// part 2 from the card by card trace
+    fn cascade_total(cards: &[Card]) -> u64 {
+        cascade_trace(cards).iter().map(|t| t.instances).sum()
+    }
+
+    #[test]
+    pub fn test_d4_bitset() {
+        let mut set: NumberSet = [3, 64, 127].into_iter().collect();
+        assert!(set.insert(0));
+        assert!(!set.insert(64));
+        assert!(set.contains(127) && !set.contains(128) && !set.contains(5));
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.iter().collect::<Vec<u32>>(), vec![0, 3, 64, 127]);
+        let other: NumberSet = [3, 127, 50].into_iter().collect();
+        assert_eq!(
+            set.intersection(&other).iter().collect::<Vec<u32>>(),
+            vec![3, 127]
+        );
+        assert!(NumberSet::new().is_empty());
+        let mut large: NumberSet = [500, 128, 3].into_iter().collect();
+        assert!(!large.insert(500) && large.insert(1000));
+        assert!(large.contains(128) && !large.contains(129));
+        assert_eq!(large.iter().collect::<Vec<u32>>(), vec![3, 128, 500, 1000]);
+        assert_eq!(
+            large.intersection(&set).iter().collect::<Vec<u32>>(),
+            vec![3]
+        );
+        let other: NumberSet = [1000, 3, 7].into_iter().collect();
+        assert_eq!(
+            large.intersection(&other).iter().collect::<Vec<u32>>(),
+            vec![3, 1000]
+        );
+        assert_eq!(large.intersection(&other).len(), 2);
+
+        let card = Card::parse(4, "Card 4: 1 128 41 | 2 128 41 41 999").unwrap();
+        assert_eq!(card.correct(), vec![128, 41, 41]);
+        assert_eq!(card.matches(), 3);
+
+        let mut rng = Rng::new(44);
+        let content: Vec<String> = (1..=2000).map(|nr| generate_card(&mut rng, nr)).collect();
+        let content = content.join("\n");
+        let cards = parse_cards(&content).unwrap();
+        for card in &cards {
+            assert_eq!(card.matches(), card.correct().len());
+        }
+        let matches = super::matches(&cards);
+        assert_eq!(
+            stream_copies(matches.iter().copied()),
+            cascade_total(&cards)
+        );
+        assert_eq!(stream_part2(content.as_bytes()), Ok(cascade_total(&cards)));
+        assert_eq!(
+            stream_part2("Card 1: 1 | 1\nCard 2: 1 1 | 2".as_bytes()),
+            Err(CardError::DuplicateWinner(2, 1))
+        );
+        assert!(matches!(
+            stream_part2(&b"Card 1: 1 | 1\n\xff\n"[..]),
+            Err(CardError::Io(_))
+        ));
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    pub fn bench_d4_stream() {
+        let mut rng = Rng::new(4);
+        let cards = 20_000_000;
+        let start = Instant::now();
+        let sum = stream_copies((0..cards).map(|_| {
+            let mut winning = NumberSet::new();
+            while winning.len() < 5 {
+                winning.insert(rng.next(100) as u32);
+            }
+            let drawn: NumberSet = (0..8).map(|_| rng.next(100) as u32).collect();
+            winning.intersection(&drawn).len()
+        }));
+        println!("{cards} generated cards: {sum} in {:?}", start.elapsed());
+
+        let content: Vec<String> = (1..=500_000)
+            .map(|nr| generate_card(&mut rng, nr))
+            .collect();
+        let content = content.join("\n");
+        let start = Instant::now();
+        let sum = stream_part2(content.as_bytes()).unwrap();
+        println!("500000 parsed cards: {sum} in {:?}", start.elapsed());
+
+        let cards = parse_cards(&content).unwrap();
+        let start = Instant::now();
+        let bitset: usize = cards.iter().map(|card| card.matches()).sum();
+        let bitset_time = start.elapsed();
+        let start = Instant::now();
+        let scan: usize = cards
+            .iter()
+            .map(|card| {
+                let winning = card.get_winning();
+                card.get_drawn()
+                    .iter()
+                    .filter(|n| winning.contains(n))
+                    .count()
+            })
+            .sum();
+        println!(
+            "matching: popcount {bitset_time:?}, scan {:?}",
+            start.elapsed()
+        );
+        assert_eq!(bitset, scan);
+    }
+
+    #[test]
+    pub fn test_d4_p2real() {
+        let file = std::fs::File::open("data/d4/input.txt").unwrap();
+        let sum = stream_part2(std::io::BufReader::new(file)).unwrap();
+        assert_eq!(sum, super::part2(String::from("data/d4/input.txt")));
+        assert_eq!(sum, 6189740);
+    }
+
+    #[test]
+    pub fn test_d4_cascade() {
+        let cards = load_cards(String::from("data/d4/test_p1.txt")).unwrap();
+        let traces = cascade_trace(&cards);
+        let instances: Vec<u64> = traces.iter().map(|t| t.instances).collect();
+        assert_eq!(instances, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(
+            traces[3].contributions,
+            vec![
+                Contribution { from: 1, copies: 1 },
+                Contribution { from: 2, copies: 2 },
+                Contribution { from: 3, copies: 4 },
+            ]
+        );
+        assert!(traces[5].contributions.is_empty());
+
+        let table = render_cascade(&traces, CascadeFormat::Table);
+        assert!(table.contains("     4       1          8  1 (+1), 2 (+2), 3 (+4)\n"));
+        assert!(table.ends_with("     6       0          1  -\ntotal: 30\n"));
+        let dot = render_cascade(&traces, CascadeFormat::Dot);
+        assert!(dot.starts_with("digraph cascade {\n"));
+        assert!(dot.contains("    c1 [label=\"Card 1\\n4 matches, 1 instances\"];\n"));
+        assert!(dot.contains("    c3 -> c5 [label=\"4\"];\n"));
+        assert_eq!(dot.matches("->").count(), 9);
+    }
+
+    #[test]
+    pub fn test_d4_scoring() {
+        let matches = [4, 2, 2, 1, 0, 0];
+        assert_eq!(Points.score(&matches), 13);
+        assert_eq!(Copies.score(&matches), 30);
+        assert_eq!(LinearPoints(3).score(&matches), 27);
+        // instances per card: 1, 1 + 1, 1 + 2, 1 + 1 + 3, 1 + 1 + 5, 1
+        assert_eq!(DecayingCopies(2).score(&matches), 19);
+        // played from card 6: 1, 1, 1, 1 + 1, 1 + 2, 1 + 2 + 3
+        assert_eq!(PreviousCopies.score(&matches), 14);
+        assert_eq!(Copies.score(&[]), 0);
+        assert_eq!(Points.score(&[64]), 1 << 63);
+        assert_eq!(Points.score(&[65]), u64::MAX);
+        assert_eq!(Points.score(&[64, 64]), u64::MAX);
+        assert_eq!(LinearPoints(u64::MAX / 2).score(&[3]), u64::MAX);
+        assert_eq!(LinearPoints(u64::MAX / 2).score(&[1, 1, 1]), u64::MAX);
+
+        for spec in ["points", "copies", "previous", "linear:3", "decay:2"] {
+            assert_eq!(parse_scoring(spec).unwrap().name(), spec);
+        }
+        assert_eq!(parse_scoring("linear").unwrap().name(), "linear:1");
+        assert_eq!(
+            score(String::from("data/d4/test_p1.txt"), &LinearPoints(3)),
+            Ok(27)
+        );
+        let errors = score(String::from("data/d4/test_invalid.txt"), &Points).unwrap_err();
+        assert_eq!(errors.len(), 6);
+        assert!(parse_scoring("decay:0").is_none());
+        assert!(parse_scoring("points:2").is_none());
+        assert!(parse_scoring("bingo").is_none());
+    }
+
+    #[test]
+    pub fn test_d4_parse() {
+        let card = Card::parse(1, "Card  12: 41 48 83 | 83 86  6 48").unwrap();
+        assert_eq!(card.get_nr(), 12);
+        assert_eq!(card.get_winning(), &[41, 48, 83]);
+        assert_eq!(card.get_drawn(), &[83, 86, 6, 48]);
+        assert_eq!(card.correct(), vec![83, 48]);
+
+        let errors = load_cards(String::from("data/d4/test_invalid.txt")).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                CardError::Numbering {
+                    line: 3,
+                    expected: 3,
+                    found: 4
+                },
+                CardError::DuplicateWinner(4, 41),
+                CardError::Columns {
+                    line: 5,
+                    expected: (5, 8),
+                    found: (5, 9)
+                },
+                CardError::InvalidNumber(6, String::from("1O")),
+                CardError::Format(7, String::from("line does not start with `Card N`")),
+                CardError::Format(8, String::from("| separator not found")),
+            ]
+        );
+        assert_eq!(
+            errors[2].to_string(),
+            "Line 5: 5 winning and 9 drawn numbers, expected 5 and 8"
+        );
+        assert!(parse_cards("Card 2: 1 | 2").is_err());
+        assert_eq!(
+            parse_cards("Card 4294967295: 1 | 2\nCard 1: 1 | 2").unwrap_err(),
+            vec![
+                CardError::Numbering {
+                    line: 1,
+                    expected: 1,
+                    found: u32::MAX
+                },
+                CardError::Numbering {
+                    line: 2,
+                    expected: u32::MAX,
+                    found: 1
+                },
+            ]
+        );
+        assert!(load_cards(String::from("data/d4/test_p1.txt")).is_ok());
+
+        let errors = load_cards(String::from("data/d4/missing.txt")).unwrap_err();
+        assert!(matches!(errors[..], [CardError::Io(_)]));
+        assert_eq!(errors[0].line(), None);
+        assert!(errors[0].to_string().starts_with("Could not read"));
+    }
 }
@@ -1,8 +1,7 @@
-use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::fs;
-use std::io::Error;
-use std::path::PathBuf;
+
+use crate::input;
+use crate::parse::{parse_fixed, parse_ints, sections, ParseError};
 
 /// the map type used key pairs to map items
 type Map = HashMap<(MapType, MapType), Vec<MapItem>>;
@@ -34,33 +33,21 @@ enum MapType {
     Unknown,
 }
 
-/// a record of the keys in the order as they appear
-static mut KEY_ORDER: Vec<(MapType, MapType)> = Vec::<(MapType, MapType)>::new();
-
-/// starting seeds for part 1
-static mut SEEDS_P1: Vec<usize> = Vec::<usize>::new();
-
-/// starting seed ranges for part 2
-static mut SEEDS_P2: Vec<ItemRange> = Vec::<ItemRange>::new();
-
-/// the mapping from keys to map items
-static mut MAPPING: Lazy<Map> = Lazy::new(|| {
-    let mut map = Map::new();
-    let _ = map.insert((MapType::Unknown, MapType::Unknown), Vec::<MapItem>::new());
-    map
-});
-/// flag indicating data is loaded
-static mut DATA_LOADED: bool = false;
+/// the seeds and the maps of the almanac
+#[derive(Debug)]
+pub struct Almanac {
+    /// starting seeds for part 1
+    seeds: Vec<usize>,
+    /// a record of the keys in the order as they appear
+    key_order: Vec<(MapType, MapType)>,
+    /// the mapping from keys to map items
+    mapping: Map,
+}
 
 impl MapItem {
-    pub fn parse(input: &str) -> Self {
-        let tmp: Vec<&str> = input.split(' ').collect();
-        assert_eq!(tmp.len(), 3);
-        MapItem {
-            dst: tmp[0].parse().unwrap(),
-            src: tmp[1].parse().unwrap(),
-            len: tmp[2].parse().unwrap(),
-        }
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let [dst, src, len] = parse_fixed::<usize, 3>(input)?;
+        Ok(MapItem { dst, src, len })
     }
 
     pub fn get_dst(&self, src: usize) -> Option<usize> {
@@ -100,245 +87,191 @@ impl MapType {
         }
     }
 
-    pub fn parse_pair(pair: &str) -> (MapType, MapType) {
-        let (src_str, dst_str) = pair.split_once("-to-").unwrap();
-        (MapType::parse(src_str), MapType::parse(dst_str))
+    pub fn parse_pair(pair: &str) -> Result<(MapType, MapType), String> {
+        match pair.split_once("-to-") {
+            Some((src_str, dst_str)) => Ok((MapType::parse(src_str), MapType::parse(dst_str))),
+            None => Err(format!("expected source-to-destination, found {pair}")),
+        }
     }
 }
 
-pub fn load_data(file_path: String) -> Result<(), Error> {
-    if unsafe { DATA_LOADED } {
-        return Ok(());
-    }
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
-
-    let result = fs::read_to_string(path);
-    if result.is_err() {
-        return Err(result.unwrap_err());
-    }
-    let content = result.unwrap();
-
-    // let mut nl_count = 0u32;
-    let mut flag = false;
+impl Almanac {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let sections = sections(content).map_err(|err| err.to_string())?;
 
-    let mut key = (MapType::Unknown, MapType::Unknown);
-
-    let mut lines = content.lines();
-    let line = lines.next();
-    // read the first line and populate seeds
-    let (_, nbrs_str) = line.unwrap().split_once(':').expect("Seeds parsing failed");
-    nbrs_str
-        .trim()
-        .split(' ')
-        .into_iter()
-        .for_each(|nbr_str| unsafe { SEEDS_P1.push(nbr_str.trim().parse().unwrap()) });
-
-    while let Some(line) = lines.next() {
-        if line.len() <= 1 {
-            // new line found, mark flag to read mapping
-            flag = true;
-            continue;
-        }
-        if flag {
-            // read mapping, unmark flag to continue read map-items
-            flag = false;
-            key = MapType::parse_pair(line.split_once(' ').unwrap().0);
-            unsafe { KEY_ORDER.push(key) };
-            continue;
-        }
-
-        unsafe {
-            // add new key if not found
-            if !MAPPING.contains_key(&key) {
-                MAPPING.insert(key, Vec::<MapItem>::new());
+        // the first section holds the seeds
+        let seeds = &sections[0];
+        let line = seeds.line;
+        let seeds = match seeds.label {
+            "seeds" => {
+                parse_ints::<usize>(seeds.inline).map_err(|err| format!("Line {line}: {err}"))?
+            }
+            label => return Err(format!("Line {line}: expected seeds, found {label}")),
+        };
+        let mut almanac = Almanac {
+            seeds,
+            key_order: Vec::new(),
+            mapping: Map::new(),
+        };
+
+        for section in &sections[1..] {
+            let pair = section.label.strip_suffix(" map").unwrap_or(section.label);
+            let key =
+                MapType::parse_pair(pair).map_err(|err| format!("Line {}: {err}", section.line))?;
+            almanac.key_order.push(key);
+            for (ii, line) in section.lines.iter().enumerate() {
+                let item = MapItem::parse(line)
+                    .map_err(|err| format!("Line {}: {err}", section.line + ii + 1))?;
+                // add new map-item to the current key
+                almanac.mapping.entry(key).or_default().push(item);
             }
-            // add new map-item to the current key
-            MAPPING.get_mut(&key).unwrap().push(MapItem::parse(line));
         }
+        Ok(almanac)
     }
-    unsafe { DATA_LOADED = true };
-    Ok(())
-}
 
-pub fn reload_seeds(file_path: String) -> Result<(), Error> {
-    if unsafe { !DATA_LOADED } {
-        load_data(file_path).expect("Could not load data");
+    /// the seeds read as pairs of start and length for part 2
+    fn seed_ranges(&self) -> Vec<ItemRange> {
+        self.seeds
+            .chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| ItemRange(pair[0], pair[0] + pair[1]))
+            .collect()
     }
 
-    unsafe { DATA_LOADED = false };
-
-    for ii in (0..unsafe { SEEDS_P1.len() }).step_by(2) {
-        unsafe {
-            SEEDS_P2.push(ItemRange {
-                0: SEEDS_P1[ii],
-                1: SEEDS_P1[ii] + SEEDS_P1[ii + 1],
-            });
-        }
+    /// the items of a map, a map without items maps every value to itself
+    fn items(&self, key: &(MapType, MapType)) -> &[MapItem] {
+        self.mapping.get(key).map_or(&[][..], Vec::as_slice)
     }
-    unsafe { DATA_LOADED = true };
-    Ok(())
-}
 
-pub fn part1(file_path: String) -> usize {
-    if unsafe { !DATA_LOADED } {
-        load_data(file_path).expect("Could not load data");
-    }
-    let mut best_location: Option<usize> = None;
-    let mut next_src: usize;
-    for seed in unsafe { &SEEDS_P1 } {
-        next_src = *seed;
-        for key in unsafe { &KEY_ORDER } {
-            // panic if key not found, should be there...
-            if unsafe { !MAPPING.contains_key(&key) } {
-                // panic!("key not found");
-                print!("{:?} not found,", key);
-                continue;
-            }
-            // try to find next destination by evaluating the sources of
-            // the items related to this key. if not found the source and
-            // destination are mapped to the same value.
-            for item in unsafe { MAPPING.get(&key).unwrap() } {
-                if let Some(ns) = item.get_dst(next_src) {
-                    next_src = ns;
-                    break;
+    pub fn part1(&self) -> usize {
+        let mut best_location: Option<usize> = None;
+        let mut next_src: usize;
+        for seed in &self.seeds {
+            next_src = *seed;
+            for key in &self.key_order {
+                // try to find next destination by evaluating the sources of
+                // the items related to this key. if not found the source and
+                // destination are mapped to the same value.
+                if let Some(item) = self
+                    .items(key)
+                    .iter()
+                    .find(|item| item.get_dst(next_src).is_some())
+                {
+                    next_src = item.get_dst(next_src).unwrap();
                 }
             }
+            best_location = Some(best_location.map_or(next_src, |bl| bl.min(next_src)));
         }
-
-        if let Some(bl) = best_location {
-            if bl > next_src {
-                best_location = Some(next_src);
-            }
-        } else {
-            best_location = Some(next_src);
-        }
+        best_location.unwrap()
     }
-    return best_location.unwrap();
-}
 
-fn part2_rec(key_offset: usize, next_src: ItemRange) -> Option<ItemRange> {
-    if key_offset >= unsafe { KEY_ORDER.len() } {
-        return Some(next_src);
-    }
-    let key = unsafe { KEY_ORDER[key_offset] };
-    let mut best_range: Option<ItemRange> = None;
-    if unsafe { !MAPPING.contains_key(&key) } {
-        println!("==== KEY NOT FOUND: {key:?} ====");
-        return None;
-    }
-    let mut next_srcs = Vec::<ItemRange>::new();
-    unsafe { MAPPING.get(&key).unwrap() }
-        .into_iter()
-        .for_each(|item| match item.get_dst_range(&next_src) {
-            Some(ns) => next_srcs.push(ns),
-            _ => (),
-        });
-    if next_srcs.len() == 0 {
-        next_srcs.push(next_src);
-    }
-    next_srcs.into_iter().for_each(|ns| {
-        if let Some(curr) = part2_rec(key_offset + 1, ns) {
-            if let Some(br) = best_range {
-                if curr.0 <= br.0 {
+    fn part2_rec(&self, key_offset: usize, next_src: ItemRange) -> Option<ItemRange> {
+        if key_offset >= self.key_order.len() {
+            return Some(next_src);
+        }
+        let key = self.key_order[key_offset];
+        let mut best_range: Option<ItemRange> = None;
+        let mut next_srcs: Vec<ItemRange> = self
+            .items(&key)
+            .iter()
+            .filter_map(|item| item.get_dst_range(&next_src))
+            .collect();
+        if next_srcs.is_empty() {
+            next_srcs.push(next_src);
+        }
+        next_srcs.into_iter().for_each(|ns| {
+            if let Some(curr) = self.part2_rec(key_offset + 1, ns) {
+                if let Some(br) = best_range {
+                    if curr.0 <= br.0 {
+                        best_range = Some(curr);
+                    }
+                } else {
                     best_range = Some(curr);
                 }
-            } else {
-                best_range = Some(curr);
             }
-        }
-    });
-    best_range.or(Some(next_src))
-}
-
-pub fn part2(file_path: String) -> usize {
-    if unsafe { !DATA_LOADED } {
-        load_data(file_path.clone()).expect("Could not load data");
+        });
+        best_range.or(Some(next_src))
     }
-    reload_seeds(file_path).expect("Could not reload seeds");
 
-    let mut best_location: Option<usize> = None;
-    for seed in unsafe { &SEEDS_P2 } {
-        let range = part2_rec(0, *seed);
-        if let Some(best_range) = range {
-            if let Some(bl) = best_location {
-                if bl > best_range.0 {
-                    best_location = Some(best_range.0);
-                }
-            } else {
-                best_location = Some(best_range.0);
+    pub fn part2(&self) -> usize {
+        let mut best_location: Option<usize> = None;
+        for seed in self.seed_ranges() {
+            if let Some(best_range) = self.part2_rec(0, seed) {
+                best_location = Some(best_location.map_or(best_range.0, |bl| bl.min(best_range.0)));
             }
         }
+        best_location.unwrap()
     }
-    return best_location.unwrap();
+}
+
+pub fn load_data(file_path: String) -> Result<Almanac, String> {
+    let content = input::read(&file_path).map_err(|err| err.to_string())?;
+    Almanac::parse(&content)
+}
+
+pub fn part1(file_path: String) -> usize {
+    load_data(file_path).expect("Could not load data").part1()
+}
+
+pub fn part2(file_path: String) -> usize {
+    load_data(file_path).expect("Could not load data").part2()
 }
 
 #[cfg(test)]
 mod test_d5 {
-    use crate::d5::{
-        load_data, reload_seeds, MapType, DATA_LOADED, KEY_ORDER, MAPPING, SEEDS_P1, SEEDS_P2,
-    };
-
-    fn reset_loaded() {
-        unsafe {
-            if DATA_LOADED {
-                // clear data if loaded in previous tests
-                DATA_LOADED = false;
-                KEY_ORDER.clear();
-                MAPPING.clear();
-                SEEDS_P1.clear();
-                SEEDS_P2.clear();
-            }
-        }
-    }
+    use crate::d5::{load_data, Almanac, MapType};
 
     #[test]
     pub fn test_d5_load() {
-        reset_loaded();
         let result = load_data(String::from("data/d5/test_p1.txt"));
         assert!(result.is_ok());
+        let almanac = result.unwrap();
 
-        assert_eq!(unsafe { SEEDS_P1.len() }, 4);
-        assert_eq!(unsafe { SEEDS_P1[0] }, 79);
-        assert_eq!(unsafe { SEEDS_P1[1] }, 14);
-        assert_eq!(unsafe { SEEDS_P1[2] }, 55);
-        assert_eq!(unsafe { SEEDS_P1[3] }, 13);
+        assert_eq!(almanac.seeds.len(), 4);
+        assert_eq!(almanac.seeds[0], 79);
+        assert_eq!(almanac.seeds[1], 14);
+        assert_eq!(almanac.seeds[2], 55);
+        assert_eq!(almanac.seeds[3], 13);
 
-        assert_eq!(unsafe { KEY_ORDER.len() }, 7);
-        assert_eq!(unsafe { KEY_ORDER[0] }, (MapType::Seed, MapType::Soil));
-        assert_eq!(
-            unsafe { KEY_ORDER[6] },
-            (MapType::Humidity, MapType::Location)
-        );
+        assert_eq!(almanac.key_order.len(), 7);
+        assert_eq!(almanac.key_order[0], (MapType::Seed, MapType::Soil));
+        assert_eq!(almanac.key_order[6], (MapType::Humidity, MapType::Location));
 
-        let result = reload_seeds(String::from("data/d5/test_p1.txt"));
-        assert!(result.is_ok());
-        assert_eq!(unsafe { SEEDS_P2.len() }, 2);
+        assert_eq!(almanac.seed_ranges().len(), 2);
+    }
+
+    #[test]
+    pub fn test_d5_empty_map() {
+        let content =
+            "seeds: 79 14 55 13\n\nseed-to-soil map:\n\nsoil-to-fertilizer map:\n0 15 37\n";
+        let almanac = Almanac::parse(content).unwrap();
+        assert_eq!(almanac.key_order.len(), 2);
+        assert_eq!(almanac.part1(), 13);
+        assert_eq!(almanac.part2(), 55);
+
+        let err = Almanac::parse("seeds: 79 14\n\nfoo map:\n0 15 37\n").unwrap_err();
+        assert_eq!(err, "Line 3: expected source-to-destination, found foo");
     }
 
     #[test]
     pub fn test_d5_p1() {
-        reset_loaded();
         let a = super::part1(String::from("data/d5/test_p1.txt"));
         assert_eq!(a, 35);
     }
     #[test]
     pub fn test_d5_p1real() {
-        reset_loaded();
         let a = super::part1(String::from("data/d5/input.txt"));
         assert_eq!(a, 323142486);
     }
 
     #[test]
     pub fn test_d5_p2() {
-        reset_loaded();
         let a = super::part2(String::from("data/d5/test_p1.txt"));
         assert_eq!(a, 46);
         //  too high on real input
     }
     #[test]
     pub fn test_d5_p2real() {
-        reset_loaded();
         let a = super::part2(String::from("data/d5/input.txt"));
         assert!(26261744 < a && a < 283658805);
         assert_eq!(a, 79874951);
@@ -1,87 +1,41 @@
-use std::fs;
 use std::iter::zip;
-use std::path::PathBuf;
 
-pub fn load_data(file_path: String) -> Result<(Vec<u32>, Vec<u32>), String> {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
-    let binding = fs::read_to_string(path).expect("Should load");
-    let lines = binding.lines().collect::<Vec<&str>>();
+use crate::input;
+use crate::parse::{joined_number, labelled, labelled_numbers};
+
+/// the two lines of the races, `Time:` and `Distance:`
+fn read_lines(file_path: String) -> Result<Vec<String>, String> {
+    let binding = input::read(&file_path).map_err(|err| err.to_string())?;
+    let lines = binding.lines().map(String::from).collect::<Vec<String>>();
     // accept only 2 lines
     if lines.len() != 2 {
         return Err(format!("Expected to read 2 lines got {}", lines.len()));
     }
-    let mut time = Vec::<u32>::new();
-    let mut dist = Vec::<u32>::new();
-    if let Some((_, time_str)) = lines[0].split_once(':') {
-        time_str.trim().split(' ').for_each(|x| {
-            if let Ok(t) = x.trim().parse() {
-                time.push(t);
-            }
-        });
-    } else {
-        return Err(format!(
-            "Could not read time string, see source: {}",
-            lines[0]
-        ));
-    }
-    if let Some((_, dist_str)) = lines[1].split_once(':') {
-        dist_str.trim().split(' ').for_each(|x| {
-            if let Ok(y) = x.trim().parse() {
-                dist.push(y);
-            }
-        });
-    } else {
+    Ok(lines)
+}
+
+pub fn load_data(file_path: String) -> Result<(Vec<u32>, Vec<u32>), String> {
+    let lines = read_lines(file_path)?;
+    let time = labelled_numbers(&lines[0], "Time").map_err(|err| format!("Line 1: {err}"))?;
+    let dist = labelled_numbers(&lines[1], "Distance").map_err(|err| format!("Line 2: {err}"))?;
+    if time.len() != dist.len() {
         return Err(format!(
-            "Could not read distance string, see source: {}",
-            lines[1]
+            "Got {} times and {} distances",
+            time.len(),
+            dist.len()
         ));
     }
     Ok((time, dist))
 }
 
 fn load_data_p2(file_path: String) -> Result<(u128, u128), String> {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
-    let binding = fs::read_to_string(path).expect("Should load file");
-    let lines = binding.lines().collect::<Vec<&str>>();
-    if lines.len() != 2 {
-        return Err(format!("Expected to read 2 lines got {}", lines.len()));
-    }
-    let mut time = 0u128;
-    let mut dist = 0u128;
-    if let Some((_, x)) = lines[0].trim().split_once(':') {
-        if let Ok(y) = x
-            .trim()
-            .split(' ')
-            .flat_map(|s| s.chars())
-            .collect::<String>()
-            .parse::<u128>()
-        {
-            time = y;
-        }
-    } else {
-        return Err(format!(
-            "Could not read time string, see source: {}",
-            lines[0]
-        ));
-    }
-    if let Some((_, x)) = lines[1].trim().split_once(':') {
-        if let Ok(y) = x
-            .trim()
-            .split(' ')
-            .flat_map(|s| s.chars())
-            .collect::<String>()
-            .parse::<u128>()
-        {
-            dist = y;
-        }
-    } else {
-        return Err(format!(
-            "Could not read distance string, see source: {}",
-            lines[1]
-        ));
-    }
+    let lines = read_lines(file_path)?;
+    let time = labelled(&lines[0], "Time")
+        .and_then(joined_number)
+        .map_err(|err| format!("Line 1: {err}"))?;
+    let dist = labelled(&lines[1], "Distance")
+        .and_then(joined_number)
+        .map_err(|err| format!("Line 2: {err}"))?;
     Ok((time, dist))
 }
 
@@ -1,7 +1,7 @@
 use core::panic;
 use std::cmp::Ordering;
-use std::fs;
-use std::path::PathBuf;
+
+use crate::input;
 
 #[derive(Debug)]
 pub struct Hand {
@@ -173,9 +173,7 @@ impl Hand {
 }
 
 pub fn load_data(file_path: String, with_jokers: bool) -> Result<Vec<Hand>, String> {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(PathBuf::from(file_path));
-    let binding = fs::read_to_string(path).expect("Should load");
+    let binding = input::read(&file_path).map_err(|err| err.to_string())?;
     let lines = binding.lines().collect::<Vec<&str>>();
     let mut hands = Vec::<Hand>::new();
     lines.into_iter().for_each(|line| {
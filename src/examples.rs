@@ -0,0 +1,162 @@
+//! The worked examples of the puzzle descriptions in `doc/dayN.md`.
+//!
+//! An example is the first indented block of a part, or the example of the
+//! previous part when the part has no block of its own or only quotes the
+//! first lines of it. The stated answer is the last number of the last
+//! paragraph before the question ending the part, ignoring parentheticals.
+//! `build.rs` turns each example into a test of its fixture and answer.
+use crate::input;
+use std::fmt;
+
+mod markdown;
+pub use markdown::{extract, Example};
+
+/// the examples of `doc/dayN.md`
+pub fn load(day: u32) -> Result<Vec<Example>, String> {
+    let markdown = input::read(&format!("doc/day{day}.md")).map_err(|err| err.to_string())?;
+    Ok(extract(&markdown, day))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Matches,
+    Diverges,
+    Missing,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Matches => write!(f, "ok"),
+            Status::Diverges => write!(f, "diverges from doc"),
+            Status::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+/// an example and the test input holding it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    pub example: Example,
+    /// `data/dN/test_pK.txt`, or the fixture of an earlier part with the
+    /// same example when the part has none of its own
+    pub path: String,
+    pub status: Status,
+}
+
+impl Fixture {
+    /// write the example to the fixture file
+    pub fn write(&self) -> Result<(), String> {
+        let path = input::resolve(&self.path).map_err(|err| err.to_string())?;
+        std::fs::write(&path, &self.example.input)
+            .map_err(|err| format!("Could not write {}: {err}", path.display()))
+    }
+}
+
+impl fmt::Display for Fixture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let example = &self.example;
+        write!(
+            f,
+            "Day {} part {}: {} {}, answer {}",
+            example.day,
+            example.part,
+            self.path,
+            self.status,
+            example.answer.as_deref().unwrap_or("not stated")
+        )
+    }
+}
+
+/// compare the examples with the fixtures under `data/`
+pub fn fixtures(examples: &[Example]) -> Vec<Fixture> {
+    let mut fixtures = Vec::<Fixture>::new();
+    for example in examples {
+        let own = format!("data/d{}/test_p{}.txt", example.day, example.part);
+        let content = input::read(&own).ok();
+        let shared = fixtures
+            .iter()
+            .find(|fixture| fixture.example.input == example.input);
+        let (path, content) = match (content, shared) {
+            (None, Some(shared)) => (shared.path.clone(), input::read(&shared.path).ok()),
+            (content, _) => (own, content),
+        };
+        let status = match content {
+            Some(content) if content == input::normalize(&example.input) => Status::Matches,
+            Some(_) => Status::Diverges,
+            None => Status::Missing,
+        };
+        fixtures.push(Fixture {
+            example: example.clone(),
+            path,
+            status,
+        });
+    }
+    fixtures
+}
+
+#[cfg(test)]
+mod test_examples {
+    use crate::examples::{extract, fixtures, load, Status};
+    use crate::solutions::{self, SOLUTIONS};
+    use crate::{input, scaffold};
+
+    /// check the fixture of an example and solve it, called by the generated
+    /// tests. unused when `doc/` is missing and nothing was generated
+    #[allow(dead_code)]
+    fn check(day: u32, part: u32) {
+        let fixture = fixtures(&load(day).unwrap())
+            .into_iter()
+            .find(|fixture| fixture.example.part == part)
+            .unwrap();
+        assert_eq!(fixture.status, Status::Matches, "{fixture}");
+        let answer = solutions::solve(day, part, fixture.path.clone());
+        assert_eq!(answer, fixture.example.answer, "{fixture}");
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/example_tests.rs"));
+
+    #[test]
+    pub fn test_examples_extract() {
+        let markdown = "## Part 1\n\nExample:\n\n    a 1\n\n    b 2\nMore.\n\nThe total is 3 (1 + 2).\n\nWhat is it?\n\n---\n## Part 2\n\nThe first line\n\n    a 1\n\nnow gives 4, so 5.\n\nWhat now?\n";
+        let examples = extract(markdown, 9);
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].input, "a 1\n\nb 2\n");
+        assert_eq!(examples[0].answer.as_deref(), Some("3"));
+        assert_eq!(examples[1].input, examples[0].input);
+        assert_eq!(examples[1].answer.as_deref(), Some("5"));
+        assert_eq!(extract("## Part 1\n\nNo example?\n", 9), vec![]);
+    }
+
+    #[test]
+    pub fn test_examples_fixtures() {
+        let mut found = 0;
+        for solution in SOLUTIONS {
+            let markdown = input::read(&format!("doc/day{}.md", solution.day)).unwrap();
+            // a new day has no examples until its description is added
+            if markdown == input::normalize(&scaffold::doc(solution.day)) {
+                continue;
+            }
+            let examples = load(solution.day).unwrap();
+            // part 2 is only added once part 1 is solved, so count the parts
+            // of the doc instead of expecting both
+            let parts = markdown.split("\n## Part").skip(1);
+            for (nn, part) in parts.enumerate() {
+                let block = part.lines().any(|line| {
+                    (line.starts_with("    ") || line.starts_with('\t')) && !line.trim().is_empty()
+                });
+                let part = nn as u32 + 1;
+                if block {
+                    assert!(
+                        examples.iter().any(|example| example.part == part),
+                        "day {} part {part}",
+                        solution.day
+                    );
+                }
+            }
+            found += examples.len();
+        }
+        // the generated tests are current
+        assert_eq!(found, GENERATED);
+    }
+}
@@ -0,0 +1,135 @@
+//! The examples of a description, without reading any files. This module
+//! is shared with `build.rs` and therefore only uses std.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    pub day: u32,
+    pub part: u32,
+    /// the lines of the block, ending with a newline
+    pub input: String,
+    pub answer: Option<String>,
+}
+
+/// an indented block or a paragraph of the description
+#[derive(Debug, PartialEq)]
+enum Element {
+    Block(Vec<String>),
+    Paragraph(String),
+}
+
+fn is_indented(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+/// blank lines inside a block belong to it when the block continues after
+/// them, anywhere else they end a paragraph
+fn elements(lines: &[&str]) -> Vec<Element> {
+    let mut elements = Vec::<Element>::new();
+    let mut paragraph = Vec::<&str>::new();
+    let mut ii = 0;
+    while ii < lines.len() {
+        let line = lines[ii];
+        if is_indented(line) && !line.trim().is_empty() {
+            if !paragraph.is_empty() {
+                elements.push(Element::Paragraph(paragraph.join(" ")));
+                paragraph.clear();
+            }
+            let mut block = Vec::<String>::new();
+            while ii < lines.len() {
+                let line = lines[ii];
+                if line.trim().is_empty() {
+                    let next = lines[ii..].iter().find(|line| !line.trim().is_empty());
+                    if !next.is_some_and(|line| is_indented(line)) {
+                        break;
+                    }
+                } else if !is_indented(line) {
+                    break;
+                }
+                block.push(line.get(4..).unwrap_or("").trim_end().to_string());
+                ii += 1;
+            }
+            elements.push(Element::Block(block));
+            continue;
+        }
+        match line.trim() {
+            "" | "---" if !paragraph.is_empty() => {
+                elements.push(Element::Paragraph(paragraph.join(" ")));
+                paragraph.clear();
+            }
+            "" | "---" => (),
+            text => paragraph.push(text),
+        }
+        ii += 1;
+    }
+    if !paragraph.is_empty() {
+        elements.push(Element::Paragraph(paragraph.join(" ")));
+    }
+    elements
+}
+
+/// the text without anything in parentheses
+fn strip_parentheticals(text: &str) -> String {
+    let mut depth = 0;
+    text.chars()
+        .filter(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = 0.max(depth - 1),
+                _ => return depth == 0,
+            }
+            false
+        })
+        .collect()
+}
+
+/// the last number of the paragraph before the closing question
+fn stated_answer(elements: &[Element]) -> Option<String> {
+    let paragraphs: Vec<&str> = elements
+        .iter()
+        .filter_map(|element| match element {
+            Element::Paragraph(text) => Some(text.as_str()),
+            Element::Block(_) => None,
+        })
+        .collect();
+    let question = paragraphs.iter().rposition(|text| text.ends_with('?'))?;
+    let text = strip_parentheticals(paragraphs.get(question.checked_sub(1)?)?);
+    text.split(|c: char| !c.is_ascii_digit())
+        .rfind(|token| !token.is_empty())
+        .map(String::from)
+}
+
+/// the examples of all parts of a description
+pub fn extract(markdown: &str, day: u32) -> Vec<Example> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let starts: Vec<usize> = (0..lines.len())
+        .filter(|ii| lines[*ii].starts_with("## Part"))
+        .collect();
+    let mut examples = Vec::<Example>::new();
+    for (nn, start) in starts.iter().enumerate() {
+        let end = starts.get(nn + 1).copied().unwrap_or(lines.len());
+        let elements = elements(&lines[start + 1..end]);
+        let block = elements.iter().find_map(|element| match element {
+            Element::Block(block) => Some(block),
+            Element::Paragraph(_) => None,
+        });
+        let previous = examples.last().map(|example: &Example| &example.input);
+        let input = match (block, previous) {
+            (Some(block), Some(previous))
+                if previous.lines().zip(block).all(|(a, b)| a == b)
+                    && block.len() < previous.lines().count() =>
+            {
+                previous.clone()
+            }
+            (Some(block), _) => block.iter().map(|line| format!("{line}\n")).collect(),
+            (None, Some(previous)) => previous.clone(),
+            (None, None) => continue,
+        };
+        examples.push(Example {
+            day,
+            part: nn as u32 + 1,
+            input,
+            answer: stated_answer(&elements),
+        });
+    }
+    examples
+}
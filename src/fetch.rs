@@ -0,0 +1,811 @@
+//! `aoc2023 fetch <day>`, download the input and description of a day into
+//! `data/dN/input.txt` and `doc/dayN.md`.
+//!
+//! The session token comes from [`SESSION_VAR`] or the `session` key of the
+//! [`CONFIG_FILE`] in the repo root, which may also set `base_url` and
+//! `min_interval` (seconds between requests):
+//!
+//! ```text
+//! session = 53616c74...
+//! base_url = http://127.0.0.1:8080
+//! ```
+//!
+//! Inputs are never fetched twice unless forced, and requests are spaced by
+//! the minimum interval, also across runs. Plain `http://` URLs are served by
+//! a std-only client, `https://` URLs by `curl`.
+use crate::{input, scaffold};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const SESSION_VAR: &str = "AOC2023_SESSION";
+pub const BASE_URL_VAR: &str = "AOC2023_BASE_URL";
+/// `key = value` lines in the repo root, kept out of git
+pub const CONFIG_FILE: &str = ".aoc2023";
+/// the time of the last request, kept out of git
+pub const STAMP_FILE: &str = ".aoc2023_last_fetch";
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+pub const DEFAULT_MIN_INTERVAL: Duration = Duration::from_secs(5);
+const USER_AGENT: &str = "aoc2023 fetch (github.com/vakuj/aoc2023)";
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// no token in the environment or the config file
+    NoSession,
+    InvalidConfig(String),
+    InvalidUrl(String),
+    InvalidDay(u32),
+    /// the request could not be made
+    Transport(String),
+    /// the puzzle is not out yet
+    NotUnlocked(u32),
+    /// the token was rejected
+    Unauthorized,
+    /// the server asks to slow down
+    RateLimited,
+    Status {
+        url: String,
+        status: u16,
+    },
+    Io(PathBuf, io::Error),
+    /// the repo root to write into was not found
+    Root(input::InputError),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::NoSession => write!(
+                f,
+                "No session token, set {SESSION_VAR} or session in {CONFIG_FILE}"
+            ),
+            FetchError::InvalidConfig(err) => write!(f, "Invalid {CONFIG_FILE}: {err}"),
+            FetchError::InvalidUrl(url) => write!(f, "Invalid url {url:?}"),
+            FetchError::InvalidDay(day) => write!(f, "Invalid day {day}, use 1 to 25"),
+            FetchError::Transport(err) => write!(f, "Request failed: {err}"),
+            FetchError::NotUnlocked(day) => write!(f, "Day {day} is not unlocked yet"),
+            FetchError::Unauthorized => write!(f, "The session token was rejected"),
+            FetchError::RateLimited => write!(f, "Rate limited by the server, retry later"),
+            FetchError::Status { url, status } => write!(f, "Got status {status} for {url}"),
+            FetchError::Io(path, err) => write!(f, "Could not access {}: {err}", path.display()),
+            FetchError::Root(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+/// the transport of the fetcher, replaceable in tests
+pub trait Http {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, FetchError>;
+}
+
+/// HTTP/1.1 over a plain TCP stream, for `http://` URLs
+pub struct TcpHttp {
+    pub timeout: Duration,
+}
+
+impl Default for TcpHttp {
+    fn default() -> Self {
+        TcpHttp {
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Http for TcpHttp {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, FetchError> {
+        let (host, path) = url
+            .strip_prefix("http://")
+            .map(|rest| {
+                rest.split_once('/')
+                    .map_or((rest, "".to_string()), |(h, p)| (h, format!("/{p}")))
+            })
+            .ok_or(FetchError::InvalidUrl(url.to_string()))?;
+        let address = match host.contains(':') {
+            true => host.to_string(),
+            false => format!("{host}:80"),
+        };
+        let transport = |err: io::Error| FetchError::Transport(format!("{address}: {err}"));
+        let mut stream = TcpStream::connect(&address).map_err(transport)?;
+        stream
+            .set_read_timeout(Some(self.timeout))
+            .map_err(transport)?;
+        let path = if path.is_empty() { "/" } else { &path };
+        let mut request = format!("GET {path} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n");
+        for (name, value) in headers {
+            request += &format!("{name}: {value}\r\n");
+        }
+        request += "\r\n";
+        stream.write_all(request.as_bytes()).map_err(transport)?;
+        let mut raw = Vec::<u8>::new();
+        stream.read_to_end(&mut raw).map_err(transport)?;
+        parse_response(&raw)
+    }
+}
+
+/// `curl`, for `https://` URLs. The headers are passed on stdin so the token
+/// does not show up in the process list.
+pub struct CurlHttp;
+
+impl Http for CurlHttp {
+    fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, FetchError> {
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--config", "-"])
+            .args(["--write-out", "\n%{http_code}", url])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| FetchError::Transport(format!("curl: {err}")))?;
+        let config: String = headers
+            .iter()
+            .map(|(name, value)| format!("header = \"{name}: {value}\"\n"))
+            .collect();
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(config.as_bytes())
+                .map_err(|err| FetchError::Transport(format!("curl: {err}")))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|err| FetchError::Transport(format!("curl: {err}")))?;
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(FetchError::Transport(err.trim().to_string()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (body, status) = stdout
+            .rsplit_once('\n')
+            .ok_or(FetchError::Transport(String::from("curl: no status")))?;
+        let status = status
+            .trim()
+            .parse()
+            .map_err(|_| FetchError::Transport(format!("curl: invalid status {status:?}")))?;
+        Ok(Response {
+            status,
+            body: body.to_string(),
+        })
+    }
+}
+
+/// the backend for a base URL
+pub fn backend(base_url: &str) -> Result<Box<dyn Http>, FetchError> {
+    match base_url.split_once("://") {
+        Some(("http", _)) => Ok(Box::<TcpHttp>::default()),
+        Some(("https", _)) => Ok(Box::new(CurlHttp)),
+        _ => Err(FetchError::InvalidUrl(base_url.to_string())),
+    }
+}
+
+/// the decoded body of a chunked transfer
+fn dechunk(mut body: &[u8]) -> Result<Vec<u8>, FetchError> {
+    let invalid = || FetchError::Transport(String::from("invalid chunked body"));
+    let mut decoded = Vec::<u8>::new();
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .ok_or_else(invalid)?;
+        let size = String::from_utf8_lossy(&body[..line_end]);
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or("").trim(), 16)
+            .map_err(|_| invalid())?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Ok(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size).ok_or_else(invalid)?);
+        body = body.get(size + 2..).ok_or_else(invalid)?;
+    }
+}
+
+/// the status and body of a raw HTTP/1.x response
+pub fn parse_response(raw: &[u8]) -> Result<Response, FetchError> {
+    let invalid = |what: &str| FetchError::Transport(format!("invalid response, {what}"));
+    let head_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or(invalid("no end of headers"))?;
+    let head = String::from_utf8_lossy(&raw[..head_end]);
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or(invalid("no status"))?;
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_lowercase()))
+        .collect();
+    let body = &raw[head_end + 4..];
+    let body = match headers.get("transfer-encoding").map(String::as_str) {
+        Some("chunked") => dechunk(body)?,
+        _ => body.to_vec(),
+    };
+    Ok(Response {
+        status,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub session: String,
+    pub base_url: String,
+    pub min_interval: Duration,
+}
+
+impl Config {
+    /// the settings of the config file, overridden by the environment
+    pub fn from_sources(
+        session_var: Option<String>,
+        base_url_var: Option<String>,
+        file: Option<&str>,
+    ) -> Result<Self, FetchError> {
+        let mut values = HashMap::<&str, &str>::new();
+        for (ii, line) in file.unwrap_or("").lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((key, value)) => values.insert(key.trim(), value.trim()),
+                None => {
+                    return Err(FetchError::InvalidConfig(format!(
+                        "Line {}: expected key = value",
+                        ii + 1
+                    )))
+                }
+            };
+        }
+        let session = session_var
+            .filter(|session| !session.trim().is_empty())
+            .or(values.get("session").map(|s| s.to_string()))
+            .filter(|session| !session.trim().is_empty())
+            .ok_or(FetchError::NoSession)?;
+        let base_url = base_url_var
+            .filter(|base_url| !base_url.trim().is_empty())
+            .or(values.get("base_url").map(|s| s.to_string()))
+            .unwrap_or(DEFAULT_BASE_URL.to_string());
+        let min_interval = match values.get("min_interval") {
+            Some(seconds) => seconds
+                .parse()
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .ok_or(FetchError::InvalidConfig(format!(
+                    "invalid min_interval {seconds:?}"
+                )))?,
+            None => DEFAULT_MIN_INTERVAL,
+        };
+        Ok(Config {
+            session: session.trim().to_string(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            min_interval,
+        })
+    }
+
+    /// the config of the repo root
+    pub fn load(root: &Path) -> Result<Self, FetchError> {
+        let path = root.join(CONFIG_FILE);
+        let file = match fs::read_to_string(&path) {
+            Ok(file) => Some(file),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(FetchError::Io(path, err)),
+        };
+        Config::from_sources(
+            std::env::var(SESSION_VAR).ok(),
+            std::env::var(BASE_URL_VAR).ok(),
+            file.as_deref(),
+        )
+    }
+}
+
+/// `&amp;` and friends as the characters they stand for
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded += &rest[..start];
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let c = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => match name.strip_prefix("#x").or(name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => name.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+            }
+            .and_then(char::from_u32),
+        });
+        match (c, entity) {
+            (Some(c), Some((_, end))) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded + rest
+}
+
+/// the parts of a puzzle page as markdown blocks: paragraphs, `* ` lists
+/// and indented code
+fn parts(html: &str) -> Vec<Vec<String>> {
+    let mut parts = Vec::<Vec<String>>::new();
+    for article in html.split("<article").skip(1) {
+        let article = article.split("</article>").next().unwrap_or("");
+        let article = article.split_once('>').map_or("", |(_, body)| body);
+        let mut blocks = Vec::<String>::new();
+        let mut items = Vec::<String>::new();
+        let mut text = String::new();
+        let mut in_pre = false;
+        let mut in_heading = false;
+        let mut rest = article;
+        while !rest.is_empty() {
+            let bounds = rest
+                .find('<')
+                .and_then(|start| Some((start, start + rest[start..].find('>')?)));
+            let (chunk, tag) = match bounds {
+                Some((start, end)) => {
+                    let (chunk, tag) = (&rest[..start], &rest[start + 1..end]);
+                    rest = &rest[end + 1..];
+                    (chunk, Some(tag))
+                }
+                None => {
+                    let chunk = rest;
+                    rest = "";
+                    (chunk, None)
+                }
+            };
+            if !in_heading {
+                text += chunk;
+            }
+            let name = tag
+                .map(|tag| tag.split_whitespace().next().unwrap_or("").to_lowercase())
+                .unwrap_or_default();
+            match name.as_str() {
+                "h2" => in_heading = true,
+                "/h2" => in_heading = false,
+                "pre" => {
+                    in_pre = true;
+                    text.clear();
+                }
+                "/pre" => {
+                    in_pre = false;
+                    let code = decode_entities(&text);
+                    let lines: Vec<String> = code
+                        .trim_end_matches('\n')
+                        .lines()
+                        .map(|line| format!("    {line}"))
+                        .collect();
+                    blocks.push(lines.join("\n"));
+                    text.clear();
+                }
+                "p" | "li" if !in_pre => text.clear(),
+                "/p" | "/li" if !in_pre => {
+                    let paragraph =
+                        decode_entities(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+                    match name.as_str() {
+                        "/li" => items.push(format!("* {paragraph}")),
+                        _ => blocks.push(paragraph),
+                    }
+                    text.clear();
+                }
+                "/ul" => {
+                    blocks.push(items.join("\n"));
+                    items.clear();
+                }
+                _ => (),
+            }
+        }
+        parts.push(blocks);
+    }
+    parts
+}
+
+/// a puzzle page in the layout of `doc/dayN.md`
+pub fn html_to_markdown(html: &str, day: u32) -> String {
+    let mut markdown = format!(
+        "# Advent of Code 2023 - Day {day}\n\nSource: https://adventofcode.com/2023/day/{day}\n"
+    );
+    for (ii, blocks) in parts(html).iter().enumerate() {
+        markdown += &format!("---\n## Part {}\n\n", ii + 1);
+        for block in blocks {
+            markdown += &format!("{block}\n\n");
+        }
+    }
+    input::normalize(&markdown)
+}
+
+/// the number of parts of a description with some text
+fn written_parts(markdown: &str) -> usize {
+    markdown
+        .split("## Part")
+        .skip(1)
+        .filter(|part| {
+            part.lines()
+                .skip(1)
+                .any(|line| !line.trim().is_empty() && line.trim() != "---")
+        })
+        .count()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Written(String),
+    Cached(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Written(path) => write!(f, "Wrote {path}"),
+            Outcome::Cached(path) => write!(f, "Kept cached {path}"),
+        }
+    }
+}
+
+pub struct Fetcher {
+    http: Box<dyn Http>,
+    config: Config,
+    root: PathBuf,
+}
+
+impl Fetcher {
+    pub fn new(http: Box<dyn Http>, config: Config, root: PathBuf) -> Self {
+        Fetcher { http, config, root }
+    }
+
+    /// wait until the minimum interval since the last request has passed
+    fn throttle(&self) -> Result<(), FetchError> {
+        let stamp = self.root.join(STAMP_FILE);
+        let now = || {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+        };
+        let last = fs::read_to_string(&stamp)
+            .ok()
+            .and_then(|micros| micros.trim().parse::<u64>().ok())
+            .map(Duration::from_micros);
+        if let Some(wait) =
+            last.and_then(|last| (last + self.config.min_interval).checked_sub(now()))
+        {
+            thread::sleep(wait.min(self.config.min_interval));
+        }
+        fs::write(&stamp, now().as_micros().to_string()).map_err(|err| FetchError::Io(stamp, err))
+    }
+
+    fn get(&self, day: u32, path: &str) -> Result<String, FetchError> {
+        self.throttle()?;
+        let url = format!("{}/2023/day/{day}{path}", self.config.base_url);
+        let cookie = format!("session={}", self.config.session);
+        let headers = [("Cookie", cookie.as_str()), ("User-Agent", USER_AGENT)];
+        let response = self.http.get(&url, &headers)?;
+        match response.status {
+            200 => Ok(response.body),
+            // the site answers a bad token on the input with 400, server
+            // errors are reported with their status
+            400 | 401 | 403 if path == "/input" => Err(FetchError::Unauthorized),
+            404 => Err(FetchError::NotUnlocked(day)),
+            429 => Err(FetchError::RateLimited),
+            status => Err(FetchError::Status { url, status }),
+        }
+    }
+
+    fn write(&self, path: &str, content: &str) -> Result<Outcome, FetchError> {
+        let full = self.root.join(path);
+        if let Some(dir) = full.parent() {
+            fs::create_dir_all(dir).map_err(|err| FetchError::Io(dir.to_path_buf(), err))?;
+        }
+        fs::write(&full, content).map_err(|err| FetchError::Io(full, err))?;
+        Ok(Outcome::Written(path.to_string()))
+    }
+
+    /// fetch the input unless cached, and the description unless the cached
+    /// one already has every part
+    pub fn fetch(&self, day: u32, force: bool) -> Result<Vec<Outcome>, FetchError> {
+        if !(1..=25).contains(&day) {
+            return Err(FetchError::InvalidDay(day));
+        }
+        let mut outcomes = Vec::<Outcome>::new();
+        let input_path = format!("data/d{day}/input.txt");
+        match self.root.join(&input_path).is_file() && !force {
+            true => outcomes.push(Outcome::Cached(input_path)),
+            false => outcomes.push(self.write(&input_path, &self.get(day, "/input")?)?),
+        }
+
+        let doc_path = format!("doc/day{day}.md");
+        let cached = fs::read_to_string(self.root.join(&doc_path)).ok();
+        // a second part appears once the first is solved, so a cached
+        // description with fewer parts is refreshed
+        let complete = cached
+            .as_deref()
+            .is_some_and(|doc| doc != scaffold::doc(day) && written_parts(doc) >= 2);
+        match complete && !force {
+            true => outcomes.push(Outcome::Cached(doc_path)),
+            false => {
+                let doc = html_to_markdown(&self.get(day, "")?, day);
+                match cached.is_some_and(|cached| written_parts(&cached) > written_parts(&doc)) {
+                    true => outcomes.push(Outcome::Cached(doc_path)),
+                    false => outcomes.push(self.write(&doc_path, &doc)?),
+                }
+            }
+        }
+        Ok(outcomes)
+    }
+}
+
+/// fetch a day into the repo root with the configured backend
+pub fn run(day: u32, force: bool) -> Result<Vec<Outcome>, FetchError> {
+    let root = input::root().map_err(FetchError::Root)?;
+    let config = Config::load(&root)?;
+    let http = backend(&config.base_url)?;
+    Fetcher::new(http, config, root).fetch(day, force)
+}
+
+#[cfg(test)]
+mod test_fetch {
+    use crate::examples;
+    use crate::fetch::{
+        html_to_markdown, parse_response, Config, FetchError, Fetcher, Http, Outcome, Response,
+        TcpHttp, DEFAULT_BASE_URL, DEFAULT_MIN_INTERVAL,
+    };
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use std::{fs, thread};
+
+    const PAGE: &str = "<html><body><main>\n<article class=\"day-desc\"><h2>--- Day 9: Sums ---</h2><p>Add the numbers of each line, then the line sums.</p>\n<p>For example:</p>\n<pre><code>1 2\n3 &lt;4&gt;\n</code></pre>\n<ul><li>The first line adds up to <code>3</code>.</li><li>The second to <code>7</code>.</li></ul>\n<p>So the total is <em>10</em> (<code>3 + 7</code>).</p>\n<p>What is the <em>total</em>?</p>\n</article>\n</main></body></html>";
+
+    fn temp_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("aoc2023_fetch_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn config(base_url: &str, min_interval: Duration) -> Config {
+        Config {
+            session: String::from("53616c7465"),
+            base_url: base_url.to_string(),
+            min_interval,
+        }
+    }
+
+    /// answers from a table and records the requested URLs and when
+    struct MockHttp {
+        pages: Vec<(&'static str, u16, &'static str)>,
+        requests: Arc<Mutex<Vec<(String, Instant)>>>,
+    }
+
+    impl Http for MockHttp {
+        fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, FetchError> {
+            assert!(headers.contains(&("Cookie", "session=53616c7465")));
+            self.requests
+                .lock()
+                .unwrap()
+                .push((url.to_string(), Instant::now()));
+            let (_, status, body) = self
+                .pages
+                .iter()
+                .find(|(path, _, _)| url.ends_with(path))
+                .copied()
+                .unwrap_or(("", 404, ""));
+            Ok(Response {
+                status,
+                body: body.to_string(),
+            })
+        }
+    }
+
+    #[test]
+    pub fn test_fetch_config() {
+        let file =
+            "# fetch\nsession = abc\nbase_url = http://127.0.0.1:8080/\nmin_interval = 0.5\n";
+        let config = Config::from_sources(None, None, Some(file)).unwrap();
+        assert_eq!(config.session, "abc");
+        assert_eq!(config.base_url, "http://127.0.0.1:8080");
+        assert_eq!(config.min_interval, Duration::from_millis(500));
+
+        let config = Config::from_sources(Some(String::from("env")), None, Some(file)).unwrap();
+        assert_eq!(config.session, "env");
+        let config = Config::from_sources(Some(String::new()), None, Some(file)).unwrap();
+        assert_eq!(config.session, "abc");
+        let config = Config::from_sources(Some(String::from("env")), None, None).unwrap();
+        assert_eq!(config.base_url, DEFAULT_BASE_URL);
+        assert_eq!(config.min_interval, DEFAULT_MIN_INTERVAL);
+
+        assert!(matches!(
+            Config::from_sources(None, None, None),
+            Err(FetchError::NoSession)
+        ));
+        assert!(matches!(
+            Config::from_sources(None, None, Some("session abc")),
+            Err(FetchError::InvalidConfig(_))
+        ));
+        for seconds in ["-1", "inf", "NaN", "1e30", "soon"] {
+            let file = format!("session = abc\nmin_interval = {seconds}\n");
+            assert!(
+                matches!(
+                    Config::from_sources(None, None, Some(&file)),
+                    Err(FetchError::InvalidConfig(_))
+                ),
+                "{seconds}"
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_fetch_response() {
+        let plain = parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\n1 2\n").unwrap();
+        assert_eq!(
+            plain,
+            Response {
+                status: 200,
+                body: String::from("1 2\n")
+            }
+        );
+        let chunked = b"HTTP/1.1 404 Not Found\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nnot\r\n6\r\n found\r\n0\r\n\r\n";
+        let chunked = parse_response(chunked).unwrap();
+        assert_eq!(
+            chunked,
+            Response {
+                status: 404,
+                body: String::from("not found")
+            }
+        );
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+    }
+
+    #[test]
+    pub fn test_fetch_markdown() {
+        let markdown = html_to_markdown(PAGE, 9);
+        assert!(markdown.starts_with("# Advent of Code 2023 - Day 9\n\nSource: https://adventofcode.com/2023/day/9\n---\n## Part 1\n\nAdd the numbers"));
+        assert!(markdown.contains("For example:\n\n    1 2\n    3 <4>\n\n* The first line adds up to 3.\n* The second to 7.\n\nSo the total"));
+        let examples = examples::extract(&markdown, 9);
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].input, "1 2\n3 <4>\n");
+        assert_eq!(examples[0].answer.as_deref(), Some("10"));
+    }
+
+    #[test]
+    pub fn test_fetch_cache() {
+        let root = temp_root("cache");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let http = MockHttp {
+            pages: vec![
+                ("/day/9/input", 200, "1 2\n3 4\n"),
+                ("/day/9", 200, PAGE),
+                ("/day/11/input", 400, ""),
+                ("/day/12/input", 502, ""),
+            ],
+            requests: requests.clone(),
+        };
+        let min_interval = Duration::from_millis(50);
+        let fetcher = Fetcher::new(
+            Box::new(http),
+            config("http://mock", min_interval),
+            root.clone(),
+        );
+
+        let outcomes = fetcher.fetch(9, false).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![
+                Outcome::Written(String::from("data/d9/input.txt")),
+                Outcome::Written(String::from("doc/day9.md"))
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("data/d9/input.txt")).unwrap(),
+            "1 2\n3 4\n"
+        );
+        // one part only, so the description is fetched again but kept
+        let outcomes = fetcher.fetch(9, false).unwrap();
+        assert_eq!(
+            outcomes[0],
+            Outcome::Cached(String::from("data/d9/input.txt"))
+        );
+        let requests = requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].0, "http://mock/2023/day/9/input");
+        // the stamp is written just before the request is sent
+        let slack = Duration::from_millis(1);
+        assert!(requests
+            .windows(2)
+            .all(|w| w[1].1 - w[0].1 + slack >= min_interval));
+
+        assert!(matches!(
+            fetcher.fetch(10, false),
+            Err(FetchError::NotUnlocked(10))
+        ));
+        assert!(matches!(
+            fetcher.fetch(11, false),
+            Err(FetchError::Unauthorized)
+        ));
+        assert!(matches!(
+            fetcher.fetch(12, false),
+            Err(FetchError::Status { status: 502, .. })
+        ));
+        assert!(matches!(
+            fetcher.fetch(26, false),
+            Err(FetchError::InvalidDay(26))
+        ));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    pub fn test_fetch_stub_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let mut requests = Vec::<String>::new();
+            for stream in listener.incoming().take(2) {
+                let mut stream = stream.unwrap();
+                let mut head = Vec::<String>::new();
+                let mut reader = BufReader::new(&stream);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    head.push(line.trim().to_string());
+                }
+                let body = match head[0].as_str() {
+                    "GET /2023/day/9/input HTTP/1.1" => "1 2\n3 4\n",
+                    _ => PAGE,
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+                requests.append(&mut head);
+            }
+            requests
+        });
+
+        let root = temp_root("stub");
+        let fetcher = Fetcher::new(
+            Box::<TcpHttp>::default(),
+            config(&base_url, Duration::ZERO),
+            root.clone(),
+        );
+        fetcher.fetch(9, false).unwrap();
+        let requests = server.join().unwrap();
+        assert!(requests.contains(&String::from("Cookie: session=53616c7465")));
+        assert!(requests.contains(&String::from("GET /2023/day/9 HTTP/1.1")));
+        assert_eq!(
+            fs::read_to_string(root.join("data/d9/input.txt")).unwrap(),
+            "1 2\n3 4\n"
+        );
+        let doc = fs::read_to_string(root.join("doc/day9.md")).unwrap();
+        assert_eq!(doc, html_to_markdown(PAGE, 9));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,364 @@
+//! A 2D grid of cells addressed by [`Coord`], shared by the grid puzzles.
+//!
+//! `x` grows to the right along a row and `y` grows downwards, so `(0, 0)` is
+//! the first character of the first line of a parsed input.
+use std::collections::VecDeque;
+use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Coord {
+    pub const UP: Coord = Coord { x: 0, y: -1 };
+    pub const DOWN: Coord = Coord { x: 0, y: 1 };
+    pub const LEFT: Coord = Coord { x: -1, y: 0 };
+    pub const RIGHT: Coord = Coord { x: 1, y: 0 };
+
+    /// the four orthogonal directions, clockwise from up
+    pub const DIRS4: [Coord; 4] = [Coord::UP, Coord::RIGHT, Coord::DOWN, Coord::LEFT];
+
+    /// the eight directions including diagonals, row by row
+    pub const DIRS8: [Coord; 8] = [
+        Coord { x: -1, y: -1 },
+        Coord { x: 0, y: -1 },
+        Coord { x: 1, y: -1 },
+        Coord { x: -1, y: 0 },
+        Coord { x: 1, y: 0 },
+        Coord { x: -1, y: 1 },
+        Coord { x: 0, y: 1 },
+        Coord { x: 1, y: 1 },
+    ];
+
+    pub fn new(x: i32, y: i32) -> Self {
+        Coord { x, y }
+    }
+
+    /// the orthogonal neighbours, not bounds checked
+    pub fn neighbours4(self) -> impl Iterator<Item = Coord> {
+        Coord::DIRS4.into_iter().map(move |dir| self + dir)
+    }
+
+    /// the neighbours including diagonals, not bounds checked
+    pub fn neighbours8(self) -> impl Iterator<Item = Coord> {
+        Coord::DIRS8.into_iter().map(move |dir| self + dir)
+    }
+
+    pub fn manhattan(&self, other: Coord) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    /// the number of king moves between the coordinates
+    pub fn chebyshev(&self, other: Coord) -> i32 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+}
+
+impl Add for Coord {
+    type Output = Coord;
+    fn add(self, other: Coord) -> Coord {
+        Coord::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Coord {
+    type Output = Coord;
+    fn sub(self, other: Coord) -> Coord {
+        Coord::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<i32> for Coord {
+    type Output = Coord;
+    fn mul(self, scale: i32) -> Coord {
+        Coord::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Coord {
+    type Output = Coord;
+    fn neg(self) -> Coord {
+        Coord::new(-self.x, -self.y)
+    }
+}
+
+/// a rectangular grid stored row by row
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: i32,
+    height: i32,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// a grid with every cell set to `fill`
+    pub fn new(width: i32, height: i32, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; (width.max(0) * height.max(0)) as usize],
+        }
+    }
+
+    /// parse one row per line, mapping each character to a cell. rows shorter
+    /// than the longest one are padded with `fill`.
+    pub fn parse(content: &str, fill: T, f: impl Fn(char) -> T) -> Self {
+        let lines: Vec<&str> = content.lines().collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut grid = Grid::new(width as i32, lines.len() as i32, fill);
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                grid.cells[y * width + x] = f(c);
+            }
+        }
+        grid
+    }
+
+    /// the grid mirrored along the main diagonal
+    pub fn transpose(&self) -> Self {
+        self.remap(self.height, self.width, |c| Coord::new(c.y, c.x))
+    }
+
+    /// the grid turned a quarter clockwise
+    pub fn rotate_cw(&self) -> Self {
+        self.remap(self.height, self.width, |c| {
+            Coord::new(c.y, self.height - 1 - c.x)
+        })
+    }
+
+    /// the grid turned a quarter counter clockwise
+    pub fn rotate_ccw(&self) -> Self {
+        self.remap(self.height, self.width, |c| {
+            Coord::new(self.width - 1 - c.y, c.x)
+        })
+    }
+
+    /// a new grid where each cell is taken from `source(coord)` in this grid
+    fn remap(&self, width: i32, height: i32, source: impl Fn(Coord) -> Coord) -> Self {
+        let mut cells = Vec::<T>::with_capacity(self.cells.len());
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(self[source(Coord::new(x, y))].clone());
+            }
+        }
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn contains(&self, coord: Coord) -> bool {
+        coord.x >= 0 && coord.y >= 0 && coord.x < self.width && coord.y < self.height
+    }
+
+    fn offset(&self, coord: Coord) -> Option<usize> {
+        match self.contains(coord) {
+            true => Some((coord.y * self.width + coord.x) as usize),
+            false => None,
+        }
+    }
+
+    /// the cell at the coordinate, `None` outside the grid
+    pub fn get(&self, coord: Coord) -> Option<&T> {
+        self.offset(coord).map(|ii| &self.cells[ii])
+    }
+
+    pub fn get_mut(&mut self, coord: Coord) -> Option<&mut T> {
+        self.offset(coord).map(|ii| &mut self.cells[ii])
+    }
+
+    /// set a cell, returns false if the coordinate is outside the grid
+    pub fn set(&mut self, coord: Coord, value: T) -> bool {
+        match self.get_mut(coord) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// all coordinates, row by row
+    pub fn coords(&self) -> impl Iterator<Item = Coord> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Coord::new(x, y)))
+    }
+
+    /// all cells with their coordinates, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Coord, &T)> {
+        self.coords().zip(self.cells.iter())
+    }
+
+    /// the cells of row `y` from left to right
+    pub fn row(&self, y: i32) -> impl Iterator<Item = &T> {
+        self.line(Coord::new(0, y), Coord::RIGHT)
+    }
+
+    /// the cells of column `x` from top to bottom
+    pub fn column(&self, x: i32) -> impl Iterator<Item = &T> {
+        self.line(Coord::new(x, 0), Coord::DOWN)
+    }
+
+    /// the cells from `start` stepping by `dir` until leaving the grid, e.g.
+    /// a diagonal with `dir` (1, 1)
+    pub fn line(&self, start: Coord, dir: Coord) -> impl Iterator<Item = &T> {
+        (0..)
+            .map(move |ii| start + dir * ii)
+            .map_while(|coord| self.get(coord))
+    }
+
+    /// the orthogonal neighbours inside the grid
+    pub fn neighbours4(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        coord.neighbours4().filter(|c| self.contains(*c))
+    }
+
+    /// the neighbours including diagonals inside the grid
+    pub fn neighbours8(&self, coord: Coord) -> impl Iterator<Item = Coord> + '_ {
+        coord.neighbours8().filter(|c| self.contains(*c))
+    }
+
+    /// the coordinates reachable from `start` through orthogonal steps
+    /// between cells accepted by `pass`, in breadth first order
+    pub fn flood_fill(&self, start: Coord, pass: impl Fn(&T) -> bool) -> Vec<Coord> {
+        let mut seen = vec![false; self.cells.len()];
+        let mut filled = Vec::<Coord>::new();
+        let mut queue = VecDeque::<Coord>::new();
+        if let Some(ii) = self.offset(start) {
+            if pass(&self.cells[ii]) {
+                seen[ii] = true;
+                queue.push_back(start);
+            }
+        }
+        while let Some(coord) = queue.pop_front() {
+            filled.push(coord);
+            for next in self.neighbours4(coord) {
+                let ii = self.offset(next).unwrap();
+                if !seen[ii] && pass(&self.cells[ii]) {
+                    seen[ii] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        filled
+    }
+}
+
+impl<T> Index<Coord> for Grid<T> {
+    type Output = T;
+
+    /// panics outside the grid, use [`Grid::get`] to check
+    fn index(&self, coord: Coord) -> &T {
+        match self.offset(coord) {
+            Some(ii) => &self.cells[ii],
+            None => panic!("{coord:?} outside {}x{} grid", self.width, self.height),
+        }
+    }
+}
+
+impl<T> IndexMut<Coord> for Grid<T> {
+    fn index_mut(&mut self, coord: Coord) -> &mut T {
+        match self.offset(coord) {
+            Some(ii) => &mut self.cells[ii],
+            None => panic!("{coord:?} outside {}x{} grid", self.width, self.height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_grid {
+    use crate::grid::{Coord, Grid};
+
+    fn chars(grid: &Grid<char>) -> String {
+        (0..grid.height())
+            .map(|y| grid.row(y).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    pub fn test_coord() {
+        let a = Coord::new(2, 3);
+        assert_eq!(a + Coord::RIGHT, Coord::new(3, 3));
+        assert_eq!(a - Coord::new(1, 1), Coord::new(1, 2));
+        assert_eq!(Coord::UP * 3, Coord::new(0, -3));
+        assert_eq!(-Coord::LEFT, Coord::RIGHT);
+        assert_eq!(a.manhattan(Coord::new(0, 0)), 5);
+        assert_eq!(a.chebyshev(Coord::new(0, 0)), 3);
+        assert_eq!(a.neighbours4().count(), 4);
+        assert!(a.neighbours8().all(|c| c.chebyshev(a) == 1));
+    }
+
+    #[test]
+    pub fn test_grid_access() {
+        let mut grid = Grid::parse("abc\ndef\ngh", '.', |c| c);
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.get(Coord::new(2, 2)), Some(&'.'));
+        assert_eq!(grid.get(Coord::new(3, 0)), None);
+        assert_eq!(grid.get(Coord::new(0, -1)), None);
+        assert_eq!(grid[Coord::new(1, 1)], 'e');
+        assert!(grid.set(Coord::new(2, 2), 'i'));
+        assert!(!grid.set(Coord::new(-1, 0), 'x'));
+        grid[Coord::new(0, 0)] = 'A';
+
+        assert_eq!(grid.row(1).collect::<String>(), "def");
+        assert_eq!(grid.column(2).collect::<String>(), "cfi");
+        assert_eq!(
+            grid.line(Coord::new(0, 0), Coord::new(1, 1))
+                .collect::<String>(),
+            "Aei"
+        );
+        assert_eq!(
+            grid.line(Coord::new(2, 0), Coord::new(-1, 1))
+                .collect::<String>(),
+            "ceg"
+        );
+        assert_eq!(grid.neighbours4(Coord::new(0, 0)).count(), 2);
+        assert_eq!(grid.neighbours8(Coord::new(0, 0)).count(), 3);
+        assert_eq!(grid.neighbours8(Coord::new(1, 1)).count(), 8);
+        let (coord, cell) = grid.iter().nth(5).unwrap();
+        assert_eq!((coord, *cell), (Coord::new(2, 1), 'f'));
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn test_grid_index_outside() {
+        let grid = Grid::new(2, 2, 0u8);
+        let _ = grid[Coord::new(2, 0)];
+    }
+
+    #[test]
+    pub fn test_grid_transform() {
+        let grid = Grid::parse("abc\ndef", ' ', |c| c);
+        assert_eq!(chars(&grid.transpose()), "ad\nbe\ncf");
+        assert_eq!(chars(&grid.rotate_cw()), "da\neb\nfc");
+        assert_eq!(chars(&grid.rotate_ccw()), "cf\nbe\nad");
+        assert_eq!(grid.rotate_cw().rotate_ccw(), grid);
+        assert_eq!(grid.rotate_cw().rotate_cw().rotate_cw().rotate_cw(), grid);
+    }
+
+    #[test]
+    pub fn test_grid_flood_fill() {
+        let grid = Grid::parse("..#..\n.##..\n#...#\n..#..", '#', |c| c);
+        let filled = grid.flood_fill(Coord::new(0, 0), |c| *c == '.');
+        assert_eq!(
+            filled,
+            vec![Coord::new(0, 0), Coord::new(1, 0), Coord::new(0, 1)]
+        );
+        assert_eq!(grid.flood_fill(Coord::new(3, 0), |c| *c == '.').len(), 11);
+        assert!(grid.flood_fill(Coord::new(2, 0), |c| *c == '.').is_empty());
+        assert!(grid.flood_fill(Coord::new(9, 9), |c| *c == '.').is_empty());
+    }
+}
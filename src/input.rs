@@ -0,0 +1,181 @@
+//! Reading the puzzle inputs.
+//!
+//! Relative paths such as `data/d1/input.txt` are resolved against the repo
+//! root, the first directory holding a `Cargo.toml` at or above the working
+//! directory, so the binary and the tests can run from any subdirectory. Set
+//! [`ROOT_VAR`] to use another root. `-` reads stdin.
+//!
+//! All content is normalized: CRLF line endings become LF and trailing
+//! whitespace is removed from every line and from the end of the input.
+use once_cell::sync::OnceCell;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// environment variable overriding the repo root
+pub const ROOT_VAR: &str = "AOC2023_ROOT";
+
+/// stdin can only be read once, so its content is kept for later reads
+static STDIN: OnceCell<String> = OnceCell::new();
+
+#[derive(Debug)]
+pub enum InputError {
+    /// no `Cargo.toml` at or above the directory
+    RootNotFound(PathBuf),
+    /// the working directory is not available
+    CurrentDir(io::Error),
+    Read(PathBuf, io::Error),
+    Stdin(io::Error),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::RootNotFound(dir) => write!(
+                f,
+                "No Cargo.toml at or above {}, set {ROOT_VAR} to the repo root",
+                dir.display()
+            ),
+            InputError::CurrentDir(err) => write!(f, "No working directory: {err}"),
+            InputError::Read(path, err) => write!(f, "Could not read {}: {err}", path.display()),
+            InputError::Stdin(err) => write!(f, "Could not read stdin: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// where an input comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+    /// content compiled into the binary, e.g. with `include_str!`
+    Embedded(&'static str),
+}
+
+impl Source {
+    /// `-` for stdin, anything else is a path
+    pub fn parse(spec: &str) -> Self {
+        match spec {
+            "-" => Source::Stdin,
+            path => Source::File(PathBuf::from(path)),
+        }
+    }
+
+    /// the normalized content
+    pub fn read(&self) -> Result<String, InputError> {
+        let content = match self {
+            Source::File(path) => {
+                let path = resolve(path)?;
+                std::fs::read_to_string(&path).map_err(|err| InputError::Read(path, err))?
+            }
+            Source::Stdin => STDIN
+                .get_or_try_init(|| {
+                    let mut content = String::new();
+                    io::stdin()
+                        .read_to_string(&mut content)
+                        .map(|_| content)
+                        .map_err(InputError::Stdin)
+                })?
+                .clone(),
+            Source::Embedded(content) => content.to_string(),
+        };
+        Ok(normalize(&content))
+    }
+}
+
+/// the repo root, from [`ROOT_VAR`] or the closest `Cargo.toml`
+pub fn root() -> Result<PathBuf, InputError> {
+    let cwd = std::env::current_dir().map_err(InputError::CurrentDir)?;
+    find_root(std::env::var_os(ROOT_VAR), cwd)
+}
+
+fn find_root(var: Option<OsString>, cwd: PathBuf) -> Result<PathBuf, InputError> {
+    if let Some(root) = var {
+        return Ok(PathBuf::from(root));
+    }
+    cwd.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+        .ok_or(InputError::RootNotFound(cwd))
+}
+
+/// absolute paths as they are, relative paths below the repo root
+pub fn resolve(path: impl AsRef<Path>) -> Result<PathBuf, InputError> {
+    let path = path.as_ref();
+    match path.is_absolute() {
+        true => Ok(path.to_path_buf()),
+        false => Ok(root()?.join(path)),
+    }
+}
+
+/// LF line endings and no trailing whitespace, ending with a newline unless
+/// empty
+pub fn normalize(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len());
+    for line in content.lines() {
+        normalized += line.trim_end();
+        normalized.push('\n');
+    }
+    let end = normalized.trim_end().len();
+    normalized.truncate(end);
+    if !normalized.is_empty() {
+        normalized.push('\n');
+    }
+    normalized
+}
+
+/// read an input given as on the command line, see [`Source::parse`]
+pub fn read(spec: &str) -> Result<String, InputError> {
+    Source::parse(spec).read()
+}
+
+#[cfg(test)]
+mod test_input {
+    use crate::input::{find_root, normalize, read, resolve, root, InputError, Source, ROOT_VAR};
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    #[test]
+    pub fn test_input_normalize() {
+        assert_eq!(normalize("a \r\nb\t\r\n\r\nc"), "a\nb\n\nc\n");
+        assert_eq!(normalize("a\n\n  \n"), "a\n");
+        assert_eq!(normalize(" \r\n"), "");
+        assert_eq!(normalize("  indented\n"), "  indented\n");
+    }
+
+    #[test]
+    pub fn test_input_read() {
+        // the root may come from the environment, the lookup is checked from
+        // the crate itself
+        let root = root().unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(find_root(std::env::var_os(ROOT_VAR), cwd).unwrap(), root);
+        assert_eq!(resolve("data").unwrap(), root.join("data"));
+        let repo = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(find_root(None, repo.join("src/d2")).unwrap(), repo);
+        assert_eq!(
+            find_root(Some(OsString::from("/srv/aoc")), root.clone()).unwrap(),
+            PathBuf::from("/srv/aoc")
+        );
+        assert!(matches!(
+            find_root(None, PathBuf::from("/")),
+            Err(InputError::RootNotFound(_))
+        ));
+        assert_eq!(resolve("/tmp/x").unwrap(), PathBuf::from("/tmp/x"));
+
+        let content = read("data/d6/test_p1.txt").unwrap();
+        assert_eq!(content, "Time:      7  15   30\nDistance:  9  40  200\n");
+        let absolute = root.join("data/d6/test_p1.txt");
+        assert_eq!(read(absolute.to_str().unwrap()).unwrap(), content);
+        assert_eq!(Source::Embedded("x \r\ny").read().unwrap(), "x\ny\n");
+        assert_eq!(Source::parse("-"), Source::Stdin);
+
+        match read("data/d6/missing.txt") {
+            Err(InputError::Read(path, _)) => assert_eq!(path, root.join("data/d6/missing.txt")),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use std::env;
-#[allow(unused)]
 use std::fs;
 #[allow(unused)]
 use std::path::{Path, PathBuf};
@@ -12,14 +11,142 @@ pub mod d4;
 pub mod d5;
 pub mod d6;
 pub mod d7;
+pub mod examples;
+pub mod fetch;
+pub mod grid;
+pub mod input;
+pub mod parse;
+#[cfg(test)]
+mod regression;
+#[cfg(test)]
+mod rng;
+pub mod scaffold;
+pub mod solutions;
+
+/// value following `flag` among the arguments after the day number
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .skip(2)
+        .position(|arg| arg == flag)
+        .and_then(|ii| args.get(ii + 3).cloned())
+}
+
+/// all values following `flag` among the arguments after the day number
+fn flag_values(args: &[String], flag: &str) -> Vec<String> {
+    args.iter()
+        .enumerate()
+        .skip(2)
+        .filter(|(_, arg)| *arg == flag)
+        .filter_map(|(ii, _)| args.get(ii + 1).cloned())
+        .collect()
+}
+
+/// whether `flag` is among the arguments after the day number
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().skip(2).any(|arg| arg == flag)
+}
+
+/// `query <expr> [--input <file>] [--colors <list>]`, run a query over day 2 games
+fn run_query(args: &[String]) {
+    let query = match args.get(2).map(|q| d2::query::Query::parse(q)) {
+        Some(Ok(query)) => query,
+        Some(Err(err)) => {
+            println!("Invalid query: {err}. Aborting...");
+            return;
+        }
+        None => {
+            println!("Supply a query, e.g. 'max.red > 12 | sum number'");
+            return;
+        }
+    };
+    let input_file = flag_value(args, "--input").unwrap_or(String::from("data/d2/input.txt"));
+    let palette = match flag_value(args, "--colors") {
+        Some(colors) => d2::Palette::parse(&colors, d2::UnknownColors::Accept),
+        None => d2::Palette::rgb(),
+    };
+    match d2::load_data(input_file, &palette) {
+        Ok(games) => match query.run(&games, &palette) {
+            Ok(answer) => print!("{answer}"),
+            Err(err) => println!("{err}. Aborting..."),
+        },
+        Err(err) => println!("{err}. Aborting..."),
+    }
+}
+
+/// `examples [day] [--write]`, compare the examples of the docs with the
+/// fixtures under `data/`, writing missing and divergent fixtures on request
+fn run_examples(args: &[String]) {
+    let days: Vec<u32> = match args.get(2).filter(|arg| !arg.starts_with("--")) {
+        Some(day) => match day.parse() {
+            Ok(day) => vec![day],
+            Err(err) => {
+                println!("Could not parse day {day} : {err}. Aborting...");
+                return;
+            }
+        },
+        None => solutions::SOLUTIONS.iter().map(|s| s.day).collect(),
+    };
+    for day in days {
+        let examples = match examples::load(day) {
+            Ok(examples) => examples,
+            Err(err) => {
+                println!("{err}. Aborting...");
+                return;
+            }
+        };
+        let mut written = Vec::<String>::new();
+        for fixture in examples::fixtures(&examples) {
+            println!("{fixture}");
+            if fixture.status != examples::Status::Matches
+                && has_flag(args, "--write")
+                && !written.contains(&fixture.path)
+            {
+                written.push(fixture.path.clone());
+                match fixture.write() {
+                    Ok(()) => println!("Wrote {}", fixture.path),
+                    Err(err) => println!("{err}"),
+                }
+            }
+        }
+    }
+}
 
 fn main() {
     let args: Vec<_> = env::args().collect();
-    if args.len() != 2 {
+    if args.len() < 2 {
         println!("Supply a day number to run");
         return;
     }
 
+    if args[1] == "query" {
+        run_query(&args);
+        return;
+    }
+    if args[1] == "new" {
+        match args.get(2).map(|day| day.parse::<u32>()) {
+            Some(Ok(day)) => match scaffold::run(day) {
+                Ok(changed) => changed.iter().for_each(|path| println!("Wrote {path}")),
+                Err(err) => println!("{err}. Aborting..."),
+            },
+            _ => println!("Supply the number of the new day"),
+        }
+        return;
+    }
+    if args[1] == "fetch" {
+        match args.get(2).map(|day| day.parse::<u32>()) {
+            Some(Ok(day)) => match fetch::run(day, has_flag(&args, "--force")) {
+                Ok(outcomes) => outcomes.iter().for_each(|outcome| println!("{outcome}")),
+                Err(err) => println!("{err}. Aborting..."),
+            },
+            _ => println!("Supply the number of the day to fetch"),
+        }
+        return;
+    }
+    if args[1] == "examples" {
+        run_examples(&args);
+        return;
+    }
+
     let day_arg = &args[1];
     let day: u32 = match day_arg.parse() {
         Ok(d) => d,
@@ -30,57 +157,286 @@ fn main() {
     };
 
     let result_str: String;
-    let input_file = format!("data/d{day}/input.txt");
+    // a file or `-` for stdin
+    let input_file = flag_value(&args, "--input").unwrap_or(format!("data/d{day}/input.txt"));
     match day {
         1 => {
-            let p1 = d1::part1(input_file.clone());
-            let p2 = d1::part2(input_file);
-            result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
+            let vocab = match flag_value(&args, "--vocab") {
+                Some(vocab_file) => match d1::Vocabulary::load(vocab_file) {
+                    Ok(vocab) => vocab,
+                    Err(err) => {
+                        println!("{err}. Aborting...");
+                        return;
+                    }
+                },
+                None => d1::Vocabulary::english(),
+            };
+            if let Some(format) = flag_value(&args, "--trace") {
+                let format = match d1::TraceFormat::parse(&format) {
+                    Some(format) => format,
+                    None => {
+                        println!("Invalid trace format {format}, use text or json. Aborting...");
+                        return;
+                    }
+                };
+                let vocab = match flag_value(&args, "--part").as_deref() {
+                    Some("1") => d1::Vocabulary::digits(),
+                    Some("2") | None => vocab,
+                    Some(part) => {
+                        println!("Invalid part {part}. Aborting...");
+                        return;
+                    }
+                };
+                match d1::trace(input_file, &vocab) {
+                    Ok(lines) => lines
+                        .iter()
+                        .for_each(|line| println!("{}", line.format(format))),
+                    Err(err) => println!("{err}. Aborting..."),
+                }
+                return;
+            }
+            let policy = match flag_value(&args, "--missing") {
+                Some(policy) => match d1::MissingPolicy::parse(&policy) {
+                    Some(policy) => policy,
+                    None => {
+                        println!("Invalid policy {policy}, use error, skip or zero. Aborting...");
+                        return;
+                    }
+                },
+                None => d1::MissingPolicy::default(),
+            };
+            let digits = d1::Vocabulary::digits();
+            let calibrations = (
+                d1::calibrate(input_file.clone(), &digits, policy),
+                d1::calibrate(input_file, &vocab, policy),
+            );
+            let (p1, p2) = match calibrations {
+                (Ok(p1), Ok(p2)) => (p1, p2),
+                (Err(err), _) | (_, Err(err)) => {
+                    println!("{err}. Aborting...");
+                    return;
+                }
+            };
+            result_str = format!(
+                "Day {}\nPart 1: {}\n{}Part 2: {}\n{}",
+                day,
+                p1.sum,
+                p1.summary(),
+                p2.sum,
+                p2.summary()
+            );
         }
         2 => {
-            let (p1, p2) = d2::both_parts(input_file);
+            let unknown = match has_flag(&args, "--reject-unknown") {
+                true => d2::UnknownColors::Reject,
+                false => d2::UnknownColors::Accept,
+            };
+            let palette = match flag_value(&args, "--colors") {
+                Some(colors) => d2::Palette::parse(&colors, unknown),
+                None => d2::Palette::new(&["red", "green", "blue"], unknown),
+            };
+            let mut bags = Vec::<d2::Set>::new();
+            for bag_str in flag_values(&args, "--bag") {
+                match d2::parse_bag(&bag_str, &palette) {
+                    Ok(bag) => bags.push(bag),
+                    Err(err) => {
+                        println!("Invalid bag {bag_str:?}: {err}. Aborting...");
+                        return;
+                    }
+                }
+            }
+            if let Some(bags_file) = flag_value(&args, "--bags") {
+                match d2::load_bags(bags_file, &palette) {
+                    Ok(mut loaded) => bags.append(&mut loaded),
+                    Err(err) => {
+                        println!("{err}. Aborting...");
+                        return;
+                    }
+                }
+            }
+            let games = match d2::load_data(input_file, &palette) {
+                Ok(games) => games,
+                Err(err) => {
+                    println!("{err}. Aborting...");
+                    return;
+                }
+            };
+            if has_flag(&args, "--stats") {
+                let bag = bags.first().cloned().unwrap_or_else(d2::default_bag);
+                print!("{}", d2::Analytics::new(&games).report(&bag, &palette));
+                return;
+            }
+            if !bags.is_empty() {
+                bags.iter()
+                    .for_each(|bag| println!("{}", d2::bag_report(&games, bag)));
+                return;
+            }
+            let p1 = d2::part1(&games, &d2::default_bag());
+            let p2 = match d2::part2(&games, &palette) {
+                Some(p2) => p2.to_string(),
+                None => String::from("overflow"),
+            };
             result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
         }
         3 => {
-            let p1 = d3::part1(input_file.clone());
-            let p2 = d3::part2(input_file);
+            if has_flag(&args, "--shared") {
+                match d3::shared_report(input_file) {
+                    Ok(report) => print!("{report}"),
+                    Err(err) => println!("{err}. Aborting..."),
+                }
+                return;
+            }
+            let mut rule = d3::GearRule::default();
+            if let Some(symbol) = flag_value(&args, "--gear-symbol") {
+                match symbol.chars().next() {
+                    Some(c) if symbol.chars().count() == 1 => rule.symbol = c,
+                    _ => {
+                        println!("Invalid gear symbol {symbol:?}. Aborting...");
+                        return;
+                    }
+                }
+            }
+            if let Some(count) = flag_value(&args, "--gear-count") {
+                match d3::GearCount::parse(&count) {
+                    Some(count) => rule.count = count,
+                    None => {
+                        println!("Invalid gear count {count}, use e.g. 2 or 2+. Aborting...");
+                        return;
+                    }
+                }
+            }
+            if let Some(op) = flag_value(&args, "--gear-op") {
+                match d3::GearOp::parse(&op) {
+                    Some(op) => rule.op = op,
+                    None => {
+                        println!("Invalid gear op {op}, use product, sum, min or max. Aborting...");
+                        return;
+                    }
+                }
+            }
+            if has_flag(&args, "--gears") {
+                match d3::gear_report(input_file, &rule) {
+                    Ok(report) => print!("{report}"),
+                    Err(err) => println!("{err}. Aborting..."),
+                }
+                return;
+            }
+            if let Some(format) = flag_value(&args, "--render") {
+                let format = match d3::RenderFormat::parse(&format) {
+                    Some(format) => format,
+                    None => {
+                        println!("Invalid render format {format}, use ansi or html. Aborting...");
+                        return;
+                    }
+                };
+                let options = d3::RenderOptions {
+                    format,
+                    rulers: has_flag(&args, "--rulers"),
+                    rule,
+                };
+                let rendered = match d3::Schematic::load(input_file) {
+                    Ok(schematic) => schematic.render(&options),
+                    Err(err) => {
+                        println!("{err}. Aborting...");
+                        return;
+                    }
+                };
+                match flag_value(&args, "--output") {
+                    Some(output) => match fs::write(&output, rendered) {
+                        Ok(()) => println!("Rendered schematic to {output}"),
+                        Err(err) => println!("Could not write {output}: {err}"),
+                    },
+                    None => print!("{rendered}"),
+                }
+                return;
+            }
+            let schematic = match d3::Schematic::load(input_file) {
+                Ok(schematic) => schematic,
+                Err(err) => {
+                    println!("{err}. Aborting...");
+                    return;
+                }
+            };
+            let p1 = schematic.part1();
+            let p2 = match schematic.gear_ratios(&rule) {
+                Some(p2) => p2.to_string(),
+                None => String::from("overflow"),
+            };
             result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
         }
         4 => {
+            if has_flag(&args, "--check") {
+                match d4::load_cards(input_file) {
+                    Ok(cards) => println!("{} cards, no violations", cards.len()),
+                    Err(errors) => errors.iter().for_each(|err| println!("{err}")),
+                }
+                return;
+            }
+            if let Some(format) = flag_value(&args, "--cascade") {
+                let format = match d4::CascadeFormat::parse(&format) {
+                    Some(format) => format,
+                    None => {
+                        println!("Invalid cascade format {format}, use table or dot. Aborting...");
+                        return;
+                    }
+                };
+                match d4::load_cards(input_file) {
+                    Ok(cards) => {
+                        print!("{}", d4::render_cascade(&d4::cascade_trace(&cards), format))
+                    }
+                    Err(errors) => errors.iter().for_each(|err| println!("{err}")),
+                }
+                return;
+            }
+            let specs = flag_values(&args, "--scoring");
+            if !specs.is_empty() {
+                for spec in specs {
+                    match d4::parse_scoring(&spec) {
+                        Some(scoring) => match d4::score(input_file.clone(), &*scoring) {
+                            Ok(score) => println!("{}: {}", scoring.name(), score),
+                            Err(errors) => {
+                                errors.iter().for_each(|err| println!("{err}"));
+                                return;
+                            }
+                        },
+                        None => {
+                            println!("Invalid scoring {spec}, use points, copies, linear[:n], decay[:n] or previous. Aborting...");
+                            return;
+                        }
+                    }
+                }
+                return;
+            }
             let p1 = d4::part1(input_file.clone());
             let p2 = d4::part2(input_file);
             result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
         }
-        5 => {
-            let p1 = d5::part1(input_file.clone());
-            let p2 = d5::part2(input_file);
-            result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
-        }
-        6 => {
-            let p1 = d6::part1(input_file.clone());
-            let p2 = d6::part2(input_file);
-            result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
-        }
-        7 => {
-            let p1 = d7::part1(input_file.clone());
-            let p2 = d7::part2(input_file);
-            result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
-        }
-        _ => {
-            println!("Invalid number {day}. Aborting...");
-            return;
-        }
+        // days without options of their own
+        _ => match solutions::get(day) {
+            Some(solution) => {
+                let p1 = (solution.part1)(input_file.clone());
+                let p2 = (solution.part2)(input_file);
+                result_str = format!("Day {}\nPart 1: {}\nPart 2: {}\n", day, p1, p2);
+            }
+            None => {
+                println!("Invalid number {day}. Aborting...");
+                return;
+            }
+        },
     }
-    let mut output_path = env::current_dir().unwrap();
-
-    let output_file = format!("output/output{day}.txt");
-    output_path.push(PathBuf::from(output_file));
-
     println!("{result_str}");
+    let output_path = match input::resolve(format!("output/output{day}.txt")) {
+        Ok(path) => path,
+        Err(err) => {
+            println!("{err}, output not written");
+            return;
+        }
+    };
     let mut file = OpenOptions::new()
         .write(true)
         .read(true)
         .create(true)
+        .truncate(true)
         .open(output_path.as_path())
         .unwrap();
     file.write_all(result_str.as_bytes()).unwrap();
@@ -0,0 +1,243 @@
+//! Helpers for the number lists, labelled lines and blank line separated
+//! sections found in the puzzle inputs.
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// a token that is not a number of the requested type
+    InvalidNumber(String),
+    /// a line without the `:` after its label
+    MissingColon(String),
+    /// a label other than the expected one
+    UnexpectedLabel { expected: String, found: String },
+    /// another count of numbers or lines than expected
+    WrongCount { expected: usize, found: usize },
+    /// nothing to parse
+    Empty,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidNumber(token) => write!(f, "invalid number {token:?}"),
+            ParseError::MissingColon(line) => write!(f, "no colon in {line:?}"),
+            ParseError::UnexpectedLabel { expected, found } => {
+                write!(f, "expected label {expected:?}, found {found:?}")
+            }
+            ParseError::WrongCount { expected, found } => {
+                write!(f, "expected {expected} items, found {found}")
+            }
+            ParseError::Empty => write!(f, "nothing to parse"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// every whitespace separated token as a number, failing on the first token
+/// that is not one
+pub fn parse_ints<T: FromStr>(list: &str) -> Result<Vec<T>, ParseError> {
+    list.split_whitespace()
+        .map(|token| {
+            token
+                .parse::<T>()
+                .map_err(|_| ParseError::InvalidNumber(token.to_string()))
+        })
+        .collect()
+}
+
+/// like [`parse_ints`], but exactly `N` numbers
+pub fn parse_fixed<T: FromStr, const N: usize>(list: &str) -> Result<[T; N], ParseError> {
+    let numbers = parse_ints::<T>(list)?;
+    let found = numbers.len();
+    numbers
+        .try_into()
+        .map_err(|_| ParseError::WrongCount { expected: N, found })
+}
+
+/// the digits of all tokens joined into one number, e.g. `7  15   30` is 71530
+pub fn joined_number<T: FromStr>(list: &str) -> Result<T, ParseError> {
+    let joined: String = list.split_whitespace().collect();
+    if joined.is_empty() {
+        return Err(ParseError::Empty);
+    }
+    joined
+        .parse::<T>()
+        .map_err(|_| ParseError::InvalidNumber(joined))
+}
+
+/// split `label: rest` at the first colon, the label is trimmed
+pub fn split_label(line: &str) -> Result<(&str, &str), ParseError> {
+    match line.split_once(':') {
+        Some((label, rest)) => Ok((label.trim(), rest)),
+        None => Err(ParseError::MissingColon(line.to_string())),
+    }
+}
+
+/// the text after `label:`, failing on any other label
+pub fn labelled<'a>(line: &'a str, label: &str) -> Result<&'a str, ParseError> {
+    match split_label(line)? {
+        (found, rest) if found == label => Ok(rest),
+        (found, _) => Err(ParseError::UnexpectedLabel {
+            expected: label.to_string(),
+            found: found.to_string(),
+        }),
+    }
+}
+
+/// the numbers after the colon of a line, whatever the label
+pub fn numbers_after_colon<T: FromStr>(line: &str) -> Result<Vec<T>, ParseError> {
+    parse_ints(split_label(line)?.1)
+}
+
+/// the numbers of a line `label: 1 2 3`
+pub fn labelled_numbers<T: FromStr>(line: &str, label: &str) -> Result<Vec<T>, ParseError> {
+    parse_ints(labelled(line, label)?)
+}
+
+/// the lines between blank lines, blank meaning empty or only whitespace
+pub fn blocks(content: &str) -> Vec<Vec<&str>> {
+    let mut blocks = Vec::<Vec<&str>>::new();
+    let mut block = Vec::<&str>::new();
+    for line in content.lines() {
+        match line.trim().is_empty() {
+            true if !block.is_empty() => blocks.push(std::mem::take(&mut block)),
+            true => (),
+            false => block.push(line),
+        }
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// a block starting with a `label: inline` line, e.g. `seeds: 79 14` or
+/// `seed-to-soil map:` followed by the lines of the map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// the line number of the label, from 1
+    pub line: usize,
+    pub label: &'a str,
+    /// the text after the colon of the label line
+    pub inline: &'a str,
+    pub lines: Vec<&'a str>,
+}
+
+/// split the content into blocks and the blocks into sections
+pub fn sections(content: &str) -> Result<Vec<Section<'_>>, ParseError> {
+    let mut sections = Vec::<Section>::new();
+    let mut previous_blank = true;
+    for (ii, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            previous_blank = true;
+            continue;
+        }
+        match (previous_blank, sections.last_mut()) {
+            (false, Some(section)) => section.lines.push(line),
+            _ => {
+                let (label, inline) = split_label(line)?;
+                sections.push(Section {
+                    line: ii + 1,
+                    label,
+                    inline,
+                    lines: Vec::new(),
+                });
+            }
+        }
+        previous_blank = false;
+    }
+    match sections.is_empty() {
+        true => Err(ParseError::Empty),
+        false => Ok(sections),
+    }
+}
+
+#[cfg(test)]
+mod test_parse {
+    use crate::parse::{
+        blocks, joined_number, labelled, labelled_numbers, numbers_after_colon, parse_fixed,
+        parse_ints, sections, ParseError, Section,
+    };
+
+    #[test]
+    pub fn test_parse_numbers() {
+        assert_eq!(parse_ints::<u32>("  7  15   30 "), Ok(vec![7, 15, 30]));
+        assert_eq!(parse_ints::<i64>("-3 4"), Ok(vec![-3, 4]));
+        assert_eq!(parse_ints::<u8>(""), Ok(vec![]));
+        assert_eq!(
+            parse_ints::<u8>("1 300 2"),
+            Err(ParseError::InvalidNumber(String::from("300")))
+        );
+        assert_eq!(parse_fixed::<usize, 3>("50 98 2"), Ok([50, 98, 2]));
+        assert_eq!(
+            parse_fixed::<usize, 3>("50 98"),
+            Err(ParseError::WrongCount {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            joined_number::<u64>("7 x"),
+            Err(ParseError::InvalidNumber(String::from("7x")))
+        );
+        assert_eq!(joined_number::<u64>(" 7  15   30"), Ok(71530));
+        assert_eq!(joined_number::<u64>("  "), Err(ParseError::Empty));
+    }
+
+    #[test]
+    pub fn test_parse_labels() {
+        assert_eq!(labelled("Time: 7 15", "Time"), Ok(" 7 15"));
+        assert_eq!(
+            labelled("Distance: 9", "Time"),
+            Err(ParseError::UnexpectedLabel {
+                expected: String::from("Time"),
+                found: String::from("Distance")
+            })
+        );
+        assert_eq!(
+            labelled_numbers::<u32>("Time 7", "Time"),
+            Err(ParseError::MissingColon(String::from("Time 7")))
+        );
+        assert_eq!(
+            numbers_after_colon::<u32>("Card 1: 41 48"),
+            Ok(vec![41, 48])
+        );
+        assert_eq!(
+            labelled_numbers::<u32>("seeds: 79 14", "seeds"),
+            Ok(vec![79, 14])
+        );
+    }
+
+    #[test]
+    pub fn test_parse_sections() {
+        let content = "\nseeds: 79 14\n\nseed-to-soil map:\n50 98 2\n52 50 48\n \n\nsoil-to-water map:\n0 15 37\n";
+        assert_eq!(
+            blocks(content),
+            vec![
+                vec!["seeds: 79 14"],
+                vec!["seed-to-soil map:", "50 98 2", "52 50 48"],
+                vec!["soil-to-water map:", "0 15 37"],
+            ]
+        );
+        let sections = sections(content).unwrap();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(
+            sections[1],
+            Section {
+                line: 4,
+                label: "seed-to-soil map",
+                inline: "",
+                lines: vec!["50 98 2", "52 50 48"],
+            }
+        );
+        assert_eq!(sections[0].inline, " 79 14");
+        assert_eq!(sections[2].line, 9);
+        assert_eq!(super::sections(" \n"), Err(ParseError::Empty));
+        assert_eq!(
+            super::sections("no label\n1 2"),
+            Err(ParseError::MissingColon(String::from("no label")))
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Regression cases found in the `data/` tree.
+//!
+//! Every `data/dN/<name>.txt` with a sidecar `data/dN/<name>.expected` is an
+//! input with known answers, one `partK: answer` line per part:
+//!
+//! ```text
+//! part1: 142
+//! part2: 281
+//! ```
+//!
+//! `build.rs` turns each (day, part, input) into a test, so a new case only
+//! needs the two files. Without `data/` no tests are generated and the build
+//! warns, a malformed sidecar fails the build. This module is shared with
+//! `build.rs` and therefore only uses std.
+use std::fs;
+use std::path::Path;
+
+pub const SIDECAR_EXTENSION: &str = "expected";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub day: u32,
+    pub part: u32,
+    /// the input, relative to the repo root
+    pub input: String,
+    pub answer: String,
+}
+
+impl Case {
+    /// a name for the case that is a valid identifier, e.g. `d1_p2_test_p2`
+    pub fn name(&self) -> String {
+        let stem = Path::new(&self.input)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stem: String = stem
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("d{}_p{}_{}", self.day, self.part, stem.to_lowercase())
+    }
+}
+
+/// the `(part, answer)` pairs of a sidecar, skipping blank lines and `#`
+/// comments
+pub fn parse_answers(content: &str) -> Result<Vec<(u32, String)>, String> {
+    let mut answers = Vec::<(u32, String)>::new();
+    for (ii, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (part, answer) = line
+            .split_once(':')
+            .ok_or(format!("Line {}: expected partK: answer", ii + 1))?;
+        let part = part
+            .trim()
+            .strip_prefix("part")
+            .and_then(|part| part.parse::<u32>().ok())
+            .ok_or(format!("Line {}: invalid part {:?}", ii + 1, part.trim()))?;
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Err(format!("Line {}: no answer for part {part}", ii + 1));
+        }
+        if answers.iter().any(|(found, _)| *found == part) {
+            return Err(format!("Line {}: part {part} given twice", ii + 1));
+        }
+        answers.push((part, answer.to_string()));
+    }
+    Ok(answers)
+}
+
+/// all cases below `root/data`, sorted by day, input and part
+pub fn discover(root: &Path) -> Result<Vec<Case>, String> {
+    let data = root.join("data");
+    let read_dir = |dir: &Path| {
+        fs::read_dir(dir).map_err(|err| format!("Could not read {}: {err}", dir.display()))
+    };
+    let mut cases = Vec::<Case>::new();
+    for entry in read_dir(&data)? {
+        let dir = entry.map_err(|err| err.to_string())?.path();
+        let day = match dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix('d'))
+            .and_then(|day| day.parse::<u32>().ok())
+        {
+            Some(day) if dir.is_dir() => day,
+            _ => continue,
+        };
+        for entry in read_dir(&dir)? {
+            let input = entry.map_err(|err| err.to_string())?.path();
+            let sidecar = input.with_extension(SIDECAR_EXTENSION);
+            if input.extension().is_none_or(|ext| ext != "txt") || !sidecar.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&sidecar)
+                .map_err(|err| format!("Could not read {}: {err}", sidecar.display()))?;
+            let answers =
+                parse_answers(&content).map_err(|err| format!("{}: {err}", sidecar.display()))?;
+            let relative = input.strip_prefix(root).unwrap_or(&input);
+            for (part, answer) in answers {
+                cases.push(Case {
+                    day,
+                    part,
+                    input: relative.to_string_lossy().replace('\\', "/"),
+                    answer,
+                });
+            }
+        }
+    }
+    cases.sort_by(|a, b| (a.day, &a.input, a.part).cmp(&(b.day, &b.input, b.part)));
+    Ok(cases)
+}
+
+#[cfg(test)]
+mod test_regression {
+    use crate::regression::{discover, parse_answers, Case};
+    use crate::{input, solutions};
+
+    /// run a discovered case, called by the generated tests. unused when
+    /// `data/` is missing and nothing was generated
+    #[allow(dead_code)]
+    fn check(day: u32, part: u32, input: &str, answer: &str) {
+        let found = solutions::solve(day, part, input.to_string());
+        assert_eq!(
+            found.as_deref(),
+            Some(answer),
+            "day {day} part {part} {input}"
+        );
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/regression_tests.rs"));
+
+    #[test]
+    pub fn test_regression_answers() {
+        assert_eq!(
+            parse_answers("# day 1\npart1: 142\n\n part2 : 281 \n"),
+            Ok(vec![(1, String::from("142")), (2, String::from("281"))])
+        );
+        assert!(parse_answers("part1 142").is_err());
+        assert!(parse_answers("p1: 142").is_err());
+        assert!(parse_answers("part1:").is_err());
+        assert!(parse_answers("part1: 1\npart1: 2").is_err());
+    }
+
+    #[test]
+    pub fn test_regression_discover() {
+        let cases = discover(&input::root().unwrap()).unwrap();
+        let case = Case {
+            day: 1,
+            part: 2,
+            input: String::from("data/d1/test_p2.txt"),
+            answer: String::from("281"),
+        };
+        assert!(cases.contains(&case));
+        assert_eq!(case.name(), "d1_p2_test_p2");
+        assert!(cases.iter().all(|case| solutions::get(case.day).is_some()));
+        assert!(!cases
+            .iter()
+            .any(|case| case.input.ends_with("vocab_sv.txt")));
+        // the generated tests are current
+        assert_eq!(cases.len(), GENERATED);
+    }
+}
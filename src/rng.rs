@@ -0,0 +1,18 @@
+//! Deterministic xorshift generator for generated test inputs.
+
+pub struct Rng(u64);
+
+impl Rng {
+    /// the seed must not be 0
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    /// a value in `0..bound`
+    pub fn next(&mut self, bound: u64) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 % bound
+    }
+}
@@ -0,0 +1,275 @@
+//! `aoc2023 new <day>`, the files of a new day and its registration in
+//! `src/main.rs` and `src/solutions.rs`.
+use crate::input;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// the module of a day, solving nothing yet
+pub fn module(day: u32) -> String {
+    format!(
+        r#"use crate::input;
+
+pub fn load_data(file_path: String) -> Result<Vec<String>, String> {{
+    let content = input::read(&file_path).map_err(|err| err.to_string())?;
+    Ok(content.lines().map(String::from).collect())
+}}
+
+pub fn part1(file_path: String) -> u32 {{
+    let _lines = load_data(file_path).expect("Could not load data");
+    0
+}}
+
+pub fn part2(file_path: String) -> u32 {{
+    let _lines = load_data(file_path).expect("Could not load data");
+    0
+}}
+
+#[cfg(test)]
+mod test_d{day} {{
+    #[test]
+    pub fn test_d{day}_load() {{
+        assert!(super::load_data(String::from("data/d{day}/test_p1.txt")).is_ok());
+    }}
+
+    #[test]
+    #[ignore = "example answer missing"]
+    pub fn test_d{day}_p1() {{
+        let a = super::part1(String::from("data/d{day}/test_p1.txt"));
+        assert_eq!(a, 0);
+    }}
+    #[test]
+    #[ignore = "example answer missing"]
+    pub fn test_d{day}_p2() {{
+        let a = super::part2(String::from("data/d{day}/test_p1.txt"));
+        assert_eq!(a, 0);
+    }}
+}}
+"#
+    )
+}
+
+/// the description of a day, without the text of the parts
+pub fn doc(day: u32) -> String {
+    format!(
+        "# Advent of Code 2023 - Day {day}\n\nSource: https://adventofcode.com/2023/day/{day}\n---\n## Part 1\n\n---\n## Part 2\n"
+    )
+}
+
+/// the day of a `pub mod dN;` line
+fn module_day(line: &str) -> Option<u32> {
+    line.strip_prefix("pub mod d")?
+        .strip_suffix(';')?
+        .parse()
+        .ok()
+}
+
+/// `main` with `pub mod dN;` among the other days, in order
+pub fn register_module(main: &str, day: u32) -> Result<String, String> {
+    let lines: Vec<&str> = main.lines().collect();
+    let days: Vec<(usize, u32)> = (0..lines.len())
+        .filter_map(|ii| module_day(lines[ii]).map(|day| (ii, day)))
+        .collect();
+    if days.iter().any(|(_, found)| *found == day) {
+        return Err(format!("Day {day} is already a module"));
+    }
+    let at = match days.iter().rev().find(|(_, found)| *found < day) {
+        Some((ii, _)) => ii + 1,
+        None => days.first().map(|(ii, _)| *ii).ok_or("No day modules")?,
+    };
+    let mut lines: Vec<String> = lines.into_iter().map(String::from).collect();
+    lines.insert(at, format!("pub mod d{day};"));
+    Ok(lines.join("\n") + "\n")
+}
+
+/// `solutions` with the day imported and added to `SOLUTIONS`, in order
+pub fn register_solution(solutions: &str, day: u32) -> Result<String, String> {
+    let start = solutions
+        .find("use crate::{")
+        .ok_or("No import of the days")?
+        + "use crate::{".len();
+    let end = start + solutions[start..].find('}').ok_or("Unterminated import")?;
+    let mut days: Vec<u32> = solutions[start..end]
+        .split(',')
+        .filter_map(|module| module.trim().strip_prefix('d')?.parse().ok())
+        .collect();
+    if days.contains(&day) {
+        return Err(format!("Day {day} is already a solution"));
+    }
+    days.push(day);
+    days.sort();
+    let import = days
+        .iter()
+        .map(|day| format!("d{day}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let marker = "    Solution {\n        day: ";
+    let list = solutions
+        .find("pub static SOLUTIONS")
+        .ok_or("No SOLUTIONS")?;
+    let list_end = list
+        + solutions[list..]
+            .find("\n];")
+            .ok_or("Unterminated SOLUTIONS")?
+        + 1;
+    let at = solutions[list..list_end]
+        .match_indices(marker)
+        .map(|(ii, _)| list + ii)
+        .find(|ii| {
+            let rest = &solutions[ii + marker.len()..];
+            let found = rest.split(',').next().and_then(|d| d.parse::<u32>().ok());
+            found.is_some_and(|found| found > day)
+        })
+        .unwrap_or(list_end);
+    let entry = format!(
+        "    Solution {{\n        day: {day},\n        part1: |f| d{day}::part1(f).to_string(),\n        part2: |f| d{day}::part2(f).to_string(),\n    }},\n"
+    );
+    Ok(format!(
+        "{}{import}{}{entry}{}",
+        &solutions[..start],
+        &solutions[end..at],
+        &solutions[at..]
+    ))
+}
+
+fn write(path: &Path, content: &str) -> Result<(), String> {
+    fs::write(path, content).map_err(|err| format!("Could not write {}: {err}", path.display()))
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("Could not read {}: {err}", path.display()))
+}
+
+/// create the files of a day below `root` and register it, returning the
+/// created and edited files. Existing docs and fixtures are kept.
+pub fn new_day(root: &Path, day: u32) -> Result<Vec<String>, String> {
+    if !(1..=25).contains(&day) {
+        return Err(format!("Invalid day {day}, use 1 to 25"));
+    }
+    let module_path = root.join(format!("src/d{day}.rs"));
+    if module_path.exists() {
+        return Err(format!("{} already exists", module_path.display()));
+    }
+    let main_path = root.join("src/main.rs");
+    let solutions_path = root.join("src/solutions.rs");
+    // edit in memory first, so nothing is written when a registration fails
+    let main = register_module(&read(&main_path)?, day)?;
+    let solutions = register_solution(&read(&solutions_path)?, day)?;
+
+    let mut changed = Vec::<String>::new();
+    let data = root.join(format!("data/d{day}"));
+    fs::create_dir_all(&data)
+        .map_err(|err| format!("Could not create {}: {err}", data.display()))?;
+    let files = [
+        (module_path, module(day)),
+        (data.join("test_p1.txt"), String::new()),
+        (root.join(format!("doc/day{day}.md")), doc(day)),
+        (main_path, main),
+        (solutions_path, solutions),
+    ];
+    for (path, content) in files {
+        let created = path.extension().is_some_and(|ext| ext != "rs");
+        if created && path.exists() {
+            continue;
+        }
+        write(&path, &content)?;
+        changed.push(
+            path.strip_prefix(root)
+                .unwrap_or(&path)
+                .display()
+                .to_string(),
+        );
+    }
+    // keep the generated sources formatted when rustfmt is around, main.rs
+    // is left out as rustfmt would format the whole crate from there
+    let _ = Command::new("rustfmt")
+        .args(["--edition", "2021"])
+        .args(
+            changed
+                .iter()
+                .filter(|path| path.ends_with(".rs") && !path.ends_with("main.rs")),
+        )
+        .current_dir(root)
+        .stderr(Stdio::null())
+        .status();
+    Ok(changed)
+}
+
+/// [`new_day`] in the repo root
+pub fn run(day: u32) -> Result<Vec<String>, String> {
+    let root = input::root().map_err(|err| err.to_string())?;
+    new_day(&root, day)
+}
+
+#[cfg(test)]
+mod test_scaffold {
+    use crate::scaffold::{doc, new_day, register_module, register_solution};
+    use crate::{examples, input, solutions};
+    use std::fs;
+
+    #[test]
+    pub fn test_scaffold_register() {
+        let main = "use std::env;\n\npub mod d1;\npub mod d3;\npub mod grid;\n\nfn main() {}\n";
+        assert_eq!(
+            register_module(main, 2).unwrap(),
+            "use std::env;\n\npub mod d1;\npub mod d2;\npub mod d3;\npub mod grid;\n\nfn main() {}\n"
+        );
+        assert_eq!(
+            register_module(main, 8).unwrap(),
+            "use std::env;\n\npub mod d1;\npub mod d3;\npub mod d8;\npub mod grid;\n\nfn main() {}\n"
+        );
+        assert!(register_module(main, 3).is_err());
+
+        let solution = |day| {
+            format!("    Solution {{\n        day: {day},\n        part1: |f| d{day}::part1(f).to_string(),\n        part2: |f| d{day}::part2(f).to_string(),\n    }},\n")
+        };
+        let solutions = format!(
+            "use crate::{{d1, d3}};\n\npub static SOLUTIONS: &[Solution] = &[\n{}{}];\n",
+            solution(1),
+            solution(3)
+        );
+        assert_eq!(
+            register_solution(&solutions, 2).unwrap(),
+            format!(
+                "use crate::{{d1, d2, d3}};\n\npub static SOLUTIONS: &[Solution] = &[\n{}{}{}];\n",
+                solution(1),
+                solution(2),
+                solution(3)
+            )
+        );
+        let registered = register_solution(&solutions, 8).unwrap();
+        assert!(registered.starts_with("use crate::{d1, d3, d8};"));
+        assert!(registered.ends_with(&format!("{}{}];\n", solution(3), solution(8))));
+        assert!(register_solution(&registered, 8).is_err());
+        assert!(register_solution("", 8).is_err());
+    }
+
+    #[test]
+    pub fn test_scaffold_new_day() {
+        let root = std::env::temp_dir().join(format!("aoc2023_scaffold_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("src")).unwrap();
+        for file in ["src/main.rs", "src/solutions.rs"] {
+            fs::copy(input::resolve(file).unwrap(), root.join(file)).unwrap();
+        }
+        fs::create_dir_all(root.join("doc")).unwrap();
+
+        let day = (1..=25).find(|day| solutions::get(*day).is_none()).unwrap();
+        let changed = new_day(&root, day).unwrap();
+        assert_eq!(changed.len(), 5);
+        let module = fs::read_to_string(root.join(format!("src/d{day}.rs"))).unwrap();
+        assert!(module.contains("pub fn part1(file_path: String) -> u32"));
+        assert!(module.contains(&format!("mod test_d{day}")));
+        assert!(root.join(format!("data/d{day}/test_p1.txt")).is_file());
+        let main = fs::read_to_string(root.join("src/main.rs")).unwrap();
+        assert!(main.contains(&format!("pub mod d{day};\n")));
+        let solutions = fs::read_to_string(root.join("src/solutions.rs")).unwrap();
+        assert!(solutions.contains(&format!("d{day}::part2(f)")));
+        assert_eq!(examples::extract(&doc(day), day), vec![]);
+
+        assert!(new_day(&root, day).is_err());
+        assert!(new_day(&root, 26).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+//! All days behind one interface, so tools can run any part of any day on
+//! any input without knowing the types of its answers.
+use crate::{d1, d2, d3, d4, d5, d6, d7};
+
+/// solves one part for an input file, panicking on invalid input
+pub type Solver = fn(String) -> String;
+
+pub struct Solution {
+    pub day: u32,
+    pub part1: Solver,
+    pub part2: Solver,
+}
+
+impl Solution {
+    /// the answer of part 1 or 2, `None` for other parts
+    pub fn solve(&self, part: u32, file_path: String) -> Option<String> {
+        match part {
+            1 => Some((self.part1)(file_path)),
+            2 => Some((self.part2)(file_path)),
+            _ => None,
+        }
+    }
+}
+
+pub static SOLUTIONS: &[Solution] = &[
+    Solution {
+        day: 1,
+        part1: |f| d1::part1(f).to_string(),
+        part2: |f| d1::part2(f).to_string(),
+    },
+    Solution {
+        day: 2,
+        part1: |f| d2::both_parts(f).0.to_string(),
+        part2: |f| d2::both_parts(f).1.to_string(),
+    },
+    Solution {
+        day: 3,
+        part1: |f| d3::part1(f).to_string(),
+        part2: |f| d3::part2(f).to_string(),
+    },
+    Solution {
+        day: 4,
+        part1: |f| d4::part1(f).to_string(),
+        part2: |f| d4::part2(f).to_string(),
+    },
+    Solution {
+        day: 5,
+        part1: |f| d5::part1(f).to_string(),
+        part2: |f| d5::part2(f).to_string(),
+    },
+    Solution {
+        day: 6,
+        part1: |f| d6::part1(f).to_string(),
+        part2: |f| d6::part2(f).to_string(),
+    },
+    Solution {
+        day: 7,
+        part1: |f| d7::part1(f).to_string(),
+        part2: |f| d7::part2(f).to_string(),
+    },
+];
+
+pub fn get(day: u32) -> Option<&'static Solution> {
+    SOLUTIONS.iter().find(|solution| solution.day == day)
+}
+
+/// the answer of a part of a day, `None` for unknown days and parts
+pub fn solve(day: u32, part: u32, file_path: String) -> Option<String> {
+    get(day)?.solve(part, file_path)
+}